@@ -0,0 +1,16 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use crate::cli::report::{process_log_files_headless, select_encounters};
+use crate::export::{export_encounters, ExportFormat};
+
+/// Parse log files and export the selected encounters to CSV, JSON or HTML
+pub fn run_export(log_files: &[PathBuf], encounter_ids: &[u64], output: &Path, format: ExportFormat, players: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let encounters = process_log_files_headless(log_files, players)?;
+    let selected = select_encounters(&encounters, encounter_ids, log_files)?;
+
+    let written = export_encounters(&selected, output, format)?;
+    for path in written {
+        println!("Exported {} encounter(s) to {}", selected.len(), path.display());
+    }
+    Ok(())
+}
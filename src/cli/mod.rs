@@ -0,0 +1,215 @@
+pub mod report;
+pub mod export;
+
+use std::error::Error;
+use std::path::PathBuf;
+use crate::export::ExportFormat;
+use crate::log::LogSource;
+
+pub use report::run_report;
+pub use export::run_export;
+
+/// Command selected from the process arguments
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Launch the overlay GUI (default when no subcommand is given)
+    Gui,
+    /// Parse log files headlessly and print per-encounter tables
+    Report {
+        /// Log files and/or folders of logs, read oldest first
+        log_files: Vec<PathBuf>,
+        encounter_ids: Vec<u64>,
+        output: Option<PathBuf>,
+        /// players.json to start from instead of an empty registry
+        players: Option<PathBuf>,
+    },
+    /// Parse log files headlessly and write encounters to CSV, JSON or HTML
+    Export {
+        /// Log files and/or folders of logs, read oldest first
+        log_files: Vec<PathBuf>,
+        encounter_ids: Vec<u64>,
+        output: PathBuf,
+        format: ExportFormat,
+        /// players.json to start from instead of an empty registry
+        players: Option<PathBuf>,
+    },
+    /// Start the overlay fed by an old log at its original pace (or `speed` times faster)
+    Replay {
+        log_file: PathBuf,
+        speed: f64,
+    },
+    /// Print usage information
+    Help,
+}
+
+pub const USAGE: &str = "Usage:
+  nwn_parser                                   Start the combat tracker overlay
+  nwn_parser report <log>... [options]         Print an encounter report for log files
+  nwn_parser export <log>... -o <file>         Export encounters to CSV, JSON or HTML
+  nwn_parser replay <log file> [-s <speed>]    Start the overlay, replaying a log as if it were being played
+
+<log> is a log file or a folder of old logs (searched recursively for nwclientLog*.txt).
+Several logs are read oldest first into one list of encounters. Players are recognised from
+the logs alone unless --players names a players.json to start from; the report goes to stdout
+and diagnostics to stderr.
+
+Report options:
+  -e, --encounter <id>    Only include the given encounter (may be repeated)
+  -o, --output <file>     Write the report to a file instead of stdout
+  -p, --players <file>    Start from this players.json (read only)
+  -h, --help              Show this help
+
+Export options:
+  -e, --encounter <id>    Only include the given encounter (may be repeated)
+  -o, --output <file>     File to write (required); CSV also writes <name>_breakdown.csv
+  -f, --format <format>   csv, json or html (default: from the file extension, else csv)
+                          HTML combines the selected encounters into one report
+  -p, --players <file>    Start from this players.json (read only)
+
+Replay options:
+  -s, --speed <factor>    Play the log this many times faster than it was written (default: 1)";
+
+impl Command {
+    /// Logs the GUI should read when this command starts it
+    pub fn log_source(&self) -> LogSource {
+        match self {
+            Command::Replay { log_file, speed } => LogSource::Replay { path: log_file.clone(), speed: *speed },
+            _ => LogSource::Live,
+        }
+    }
+}
+
+/// Parse command line arguments (excluding the program name)
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some(subcommand) = args.first() else {
+        return Ok(Command::Gui);
+    };
+
+    match subcommand.as_str() {
+        "report" => {
+            let options = parse_log_options(&args[1..], "report", false)?;
+            if options.help {
+                return Ok(Command::Help);
+            }
+            let LogOptions { log_files, encounter_ids, output, players, .. } = options;
+            Ok(Command::Report { log_files, encounter_ids, output, players })
+        }
+        "export" => {
+            let options = parse_log_options(&args[1..], "export", true)?;
+            if options.help {
+                return Ok(Command::Help);
+            }
+            let LogOptions { log_files, encounter_ids, output, players, format, .. } = options;
+            let output = output.ok_or("export requires --output <file>")?;
+            let format = format
+                .or_else(|| ExportFormat::from_path(&output))
+                .unwrap_or(ExportFormat::Csv);
+            Ok(Command::Export { log_files, encounter_ids, output, format, players })
+        }
+        "replay" => {
+            let mut log_file = None;
+            let mut speed = 1.0;
+
+            let mut iter = args[1..].iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "-s" | "--speed" => {
+                        let value = iter.next().ok_or("--speed requires a speed factor")?;
+                        speed = value.parse::<f64>().ok()
+                            .filter(|speed| *speed > 0.0)
+                            .ok_or_else(|| format!("Invalid speed '{}'", value))?;
+                    }
+                    "-h" | "--help" => return Ok(Command::Help),
+                    other if other.starts_with('-') => {
+                        return Err(format!("Unknown option '{}'", other));
+                    }
+                    other => {
+                        if log_file.is_some() {
+                            return Err(format!("Unexpected argument '{}'", other));
+                        }
+                        log_file = Some(PathBuf::from(other));
+                    }
+                }
+            }
+
+            let log_file = log_file.ok_or("replay requires a log file path")?;
+            Ok(Command::Replay { log_file, speed })
+        }
+        "-h" | "--help" | "help" => Ok(Command::Help),
+        other => Err(format!("Unknown command '{}'", other)),
+    }
+}
+
+/// Options the `report` and `export` subcommands share
+#[derive(Debug, Default)]
+struct LogOptions {
+    log_files: Vec<PathBuf>,
+    encounter_ids: Vec<u64>,
+    output: Option<PathBuf>,
+    players: Option<PathBuf>,
+    /// Only accepted by `export`
+    format: Option<ExportFormat>,
+    help: bool,
+}
+
+/// Parse the arguments after `report` or `export`
+fn parse_log_options(args: &[String], subcommand: &str, accept_format: bool) -> Result<LogOptions, String> {
+    let mut options = LogOptions::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-e" | "--encounter" => {
+                let value = iter.next().ok_or("--encounter requires an encounter id")?;
+                let id = value.parse::<u64>()
+                    .map_err(|_| format!("Invalid encounter id '{}'", value))?;
+                options.encounter_ids.push(id);
+            }
+            "-o" | "--output" => {
+                let value = iter.next().ok_or("--output requires a file path")?;
+                options.output = Some(PathBuf::from(value));
+            }
+            "-p" | "--players" => {
+                let value = iter.next().ok_or("--players requires a file path")?;
+                options.players = Some(PathBuf::from(value));
+            }
+            "-f" | "--format" if accept_format => {
+                let value = iter.next().ok_or("--format requires csv, json or html")?;
+                options.format = Some(ExportFormat::from_name(value)
+                    .ok_or_else(|| format!("Unknown export format '{}' (expected csv, json or html)", value))?);
+            }
+            "-h" | "--help" => {
+                options.help = true;
+                return Ok(options);
+            }
+            other if other.starts_with('-') => {
+                return Err(format!("Unknown option '{}'", other));
+            }
+            other => options.log_files.push(PathBuf::from(other)),
+        }
+    }
+
+    if options.log_files.is_empty() {
+        return Err(format!("{} requires a log file or folder", subcommand));
+    }
+    Ok(options)
+}
+
+/// Run a headless command. Returns `Ok(false)` if the GUI should be started instead.
+pub fn run(command: Command) -> Result<bool, Box<dyn Error>> {
+    match command {
+        Command::Gui | Command::Replay { .. } => Ok(false),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(true)
+        }
+        Command::Report { log_files, encounter_ids, output, players } => {
+            run_report(&log_files, &encounter_ids, output.as_deref(), players.as_deref())?;
+            Ok(true)
+        }
+        Command::Export { log_files, encounter_ids, output, format, players } => {
+            run_export(&log_files, &encounter_ids, &output, format, players.as_deref())?;
+            Ok(true)
+        }
+    }
+}
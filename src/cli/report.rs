@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use crate::log::finder::collect_log_files;
+use crate::models::{Encounter, CombatantStats};
+use crate::session::CombatSession;
+use crate::models::PlayerRegistry;
+use crate::utils::read_player_registry;
+
+/// Longest combatant name shown before truncation in report tables
+const MAX_NAME_WIDTH: usize = 32;
+
+/// Parse a whole log file without the GUI and return the resulting encounters
+pub fn process_log_file_headless(file_path: &Path) -> io::Result<HashMap<u64, Encounter>> {
+    process_log_files_headless(&[file_path.to_path_buf()], None)
+}
+
+/// Parse log files and folders of logs, oldest first, into one set of encounters. Players are
+/// learned from the logs alone unless a `players.json` to start from is given.
+pub fn process_log_files_headless(paths: &[PathBuf], players: Option<&Path>) -> io::Result<HashMap<u64, Encounter>> {
+    let log_files = collect_log_files(paths)?;
+    if log_files.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("No log files found in {}", describe_logs(paths))));
+    }
+
+    let mut session = CombatSession::new();
+    let registry = match players {
+        Some(path) => read_player_registry(path)?,
+        None => PlayerRegistry::new(),
+    };
+    session.player_registry = Arc::new(Mutex::new(registry));
+
+    for file_path in &log_files {
+        let file_content = fs::read(file_path)?;
+        let content_str = String::from_utf8_lossy(&file_content);
+
+        session.set_log_file(file_path);
+        for line in content_str.lines() {
+            session.feed_historical_line(line);
+        }
+    }
+    session.finish_historical();
+
+    Ok(session.snapshot().encounters)
+}
+
+/// e.g. "logs/nwclientLog1.txt, logs/archive"
+fn describe_logs(paths: &[PathBuf]) -> String {
+    paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Pick the requested encounters (all of them if `encounter_ids` is empty), sorted by id
+pub(crate) fn select_encounters<'a>(
+    encounters: &'a HashMap<u64, Encounter>,
+    encounter_ids: &[u64],
+    log_files: &[PathBuf],
+) -> Result<Vec<&'a Encounter>, Box<dyn Error>> {
+    let mut selected: Vec<&Encounter> = encounters.values()
+        .filter(|e| encounter_ids.is_empty() || encounter_ids.contains(&e.id))
+        .collect();
+    selected.sort_by_key(|e| e.id);
+
+    if selected.is_empty() && !encounter_ids.is_empty() {
+        return Err(format!("None of the requested encounters {:?} exist in {}", encounter_ids, describe_logs(log_files)).into());
+    }
+    Ok(selected)
+}
+
+/// Parse log files and print (or write) a per-encounter report
+pub fn run_report(log_files: &[PathBuf], encounter_ids: &[u64], output: Option<&Path>, players: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let encounters = process_log_files_headless(log_files, players)?;
+    let selected = select_encounters(&encounters, encounter_ids, log_files)?;
+
+    let report = format_report(&selected);
+
+    match output {
+        Some(path) => {
+            fs::write(path, report)?;
+            println!("Wrote report for {} encounter(s) to {}", selected.len(), path.display());
+        }
+        None => print!("{}", report),
+    }
+
+    Ok(())
+}
+
+/// Format a list of encounters as plain-text tables
+pub fn format_report(encounters: &[&Encounter]) -> String {
+    if encounters.is_empty() {
+        return "No encounters found.\n".to_string();
+    }
+
+    let mut out = String::new();
+    for encounter in encounters {
+        out.push_str(&format_encounter(encounter));
+        out.push('\n');
+    }
+    out
+}
+
+/// Format one encounter as a damage done/taken and attack table
+pub fn format_encounter(encounter: &Encounter) -> String {
+    let mut combatants: Vec<(&String, &CombatantStats)> = encounter.stats.iter().collect();
+    combatants.sort_by(|a, b| {
+        b.1.total_damage_dealt.cmp(&a.1.total_damage_dealt)
+            .then(b.1.total_damage_received.cmp(&a.1.total_damage_received))
+            .then(a.0.cmp(b.0))
+    });
+
+    let name_width = combatants.iter()
+        .map(|(name, _)| name.chars().count().min(MAX_NAME_WIDTH))
+        .max()
+        .unwrap_or(0)
+        .max("Combatant".len());
+
+    let mut lines = Vec::new();
+    lines.push(format!("=== {} ===", encounter.get_display_name()));
+    lines.push(format!("Time: {}", encounter.time_range()));
+    lines.push(format!("Total damage: {}", encounter.total_damage));
+    lines.push(String::new());
+    lines.push(format!(
+        "{:<name_width$}  {:>9}  {:>7}  {:>9}  {:>7}  {:>7}  {:>7}  {:>5}  {:>5}  {:>5}  {:>6}",
+        "Combatant", "Dmg Done", "DPS", "Dmg Taken", "DTPS", "Healing", "HPS", "Hits", "Miss", "Crit", "Hit%",
+        name_width = name_width
+    ));
+    lines.push("-".repeat(name_width + 91));
+
+    for (name, stats) in combatants {
+        let display_name: String = if name.chars().count() > MAX_NAME_WIDTH {
+            let truncated: String = name.chars().take(MAX_NAME_WIDTH - 3).collect();
+            format!("{}...", truncated)
+        } else {
+            name.clone()
+        };
+
+        let hit_rate = if let Some(rate) = stats.hit_rate() {
+            format!("{:.1}%", rate)
+        } else {
+            "-".to_string()
+        };
+
+        lines.push(format!(
+            "{:<name_width$}  {:>9}  {:>7}  {:>9}  {:>7}  {:>7}  {:>7}  {:>5}  {:>5}  {:>5}  {:>6}",
+            display_name,
+            stats.total_damage_dealt,
+            format_rate(stats.calculate_dps()),
+            stats.total_damage_received,
+            format_rate(stats.calculate_dtps()),
+            stats.healing_done,
+            format_rate(stats.calculate_hps()),
+            stats.hits,
+            stats.misses,
+            stats.critical_hits,
+            hit_rate,
+            name_width = name_width
+        ));
+    }
+
+    if !encounter.deaths.is_empty() {
+        lines.push(String::new());
+        lines.push("Deaths:".to_string());
+        for death in &encounter.deaths {
+            lines.push(format!("  +{:<5} {}", format_offset(encounter, death.timestamp), death.summary()));
+            for event in &death.recap {
+                lines.push(format!("      +{:<5} {}", format_offset(encounter, event.timestamp), event.describe()));
+            }
+        }
+    }
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Seconds since the start of the encounter, e.g. "12s"
+fn format_offset(encounter: &Encounter, timestamp: u64) -> String {
+    format!("{}s", timestamp.saturating_sub(encounter.start_time))
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map(|r| format!("{:.1}", r)).unwrap_or_else(|| "-".to_string())
+}
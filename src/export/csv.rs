@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::models::Encounter;
+use crate::utils::time::format_date_time;
+
+const SUMMARY_HEADER: &[&str] = &[
+    "encounter_id", "encounter", "start_time", "end_time", "duration",
+    "combatant", "damage_dealt", "dps", "hit_damage", "crit_damage", "weapon_buff_damage",
+    "hits", "misses", "critical_hits", "concealment_dodges", "weapon_buffs",
+    "damage_received", "dtps", "times_attacked", "damage_absorbed",
+    "healing_done", "effective_healing", "overhealing", "hps", "healing_received",
+    "kills", "deaths",
+    "attack_rolls", "avg_attack_bonus", "max_attack_bonus", "natural_ones", "natural_twenties",
+    "sneak_attacks", "sneak_attack_damage", "death_attacks", "death_attack_damage", "off_hand_hits", "off_hand_damage",
+];
+
+const BREAKDOWN_HEADER: &[&str] = &[
+    "encounter_id", "combatant", "kind", "target", "source", "damage_type", "amount",
+];
+
+/// Path of the per-type breakdown file written next to a summary CSV
+pub fn breakdown_path(summary_path: &Path) -> PathBuf {
+    let stem = summary_path.file_stem().and_then(|s| s.to_str()).unwrap_or("encounters");
+    summary_path.with_file_name(format!("{}_breakdown.csv", stem))
+}
+
+/// Write a one-row-per-combatant summary to `path` and the nested damage maps
+/// as long-format rows to `<stem>_breakdown.csv`.
+pub fn write_csv(encounters: &[&Encounter], path: &Path) -> io::Result<Vec<PathBuf>> {
+    let breakdown = breakdown_path(path);
+    fs::write(path, summary_csv(encounters))?;
+    fs::write(&breakdown, breakdown_csv(encounters))?;
+    Ok(vec![path.to_path_buf(), breakdown])
+}
+
+pub fn summary_csv(encounters: &[&Encounter]) -> String {
+    let mut out = csv_row(SUMMARY_HEADER.iter().map(|s| s.to_string()));
+
+    for encounter in encounters {
+        let display_name = encounter.get_display_name();
+        for (name, stats) in sorted(&encounter.stats) {
+            out.push_str(&csv_row([
+                encounter.id.to_string(),
+                display_name.clone(),
+                format_date_time(encounter.start_time),
+                format_date_time(encounter.end_time),
+                encounter.duration().to_string(),
+                name.clone(),
+                stats.total_damage_dealt.to_string(),
+                format_rate(stats.calculate_dps()),
+                stats.hit_damage.to_string(),
+                stats.crit_damage.to_string(),
+                stats.weapon_buff_damage.to_string(),
+                stats.hits.to_string(),
+                stats.misses.to_string(),
+                stats.critical_hits.to_string(),
+                stats.concealment_dodges.to_string(),
+                stats.weapon_buffs.to_string(),
+                stats.total_damage_received.to_string(),
+                format_rate(stats.calculate_dtps()),
+                stats.times_attacked.to_string(),
+                stats.total_damage_absorbed.to_string(),
+                stats.healing_done.to_string(),
+                stats.effective_healing_done.to_string(),
+                stats.overhealing_done.to_string(),
+                format_rate(stats.calculate_hps()),
+                stats.healing_received.to_string(),
+                stats.kills.to_string(),
+                stats.deaths.to_string(),
+                stats.attack_rolls.to_string(),
+                format_rate(stats.average_attack_bonus()),
+                stats.max_attack_bonus.map(|b| b.to_string()).unwrap_or_default(),
+                stats.natural_ones.to_string(),
+                stats.natural_twenties.to_string(),
+                stats.sneak_attacks.to_string(),
+                stats.sneak_attack_damage.to_string(),
+                stats.death_attacks.to_string(),
+                stats.death_attack_damage.to_string(),
+                stats.off_hand_hits.to_string(),
+                stats.off_hand_damage.to_string(),
+            ]));
+        }
+    }
+    out
+}
+
+/// Long-format rows, one per (combatant, kind, target, source, damage type).
+///
+/// `kind` is `dealt` for all outgoing damage, `hit`/`crit`/`weapon_buff` for the
+/// attack-only split, `absorbed` for damage the combatant's immunities soaked,
+/// `absorbed_resistance`/`absorbed_reduction` (with no damage type) for what its
+/// damage resistance and reduction soaked, and
+/// `healed`/`healing_source` for healing done by target and by spell.
+pub fn breakdown_csv(encounters: &[&Encounter]) -> String {
+    let mut out = csv_row(BREAKDOWN_HEADER.iter().map(|s| s.to_string()));
+
+    for encounter in encounters {
+        for (name, stats) in sorted(&encounter.stats) {
+            let mut push = |kind: &str, target: &str, source: &str, damage_type: &str, amount: u32| {
+                out.push_str(&csv_row([
+                    encounter.id.to_string(),
+                    name.clone(),
+                    kind.to_string(),
+                    target.to_string(),
+                    source.to_string(),
+                    damage_type.to_string(),
+                    amount.to_string(),
+                ]));
+            };
+
+            for (target, by_source) in sorted(&stats.damage_by_target_source_and_type_dealt) {
+                for (source, by_type) in sorted(by_source) {
+                    for (damage_type, amount) in sorted(by_type) {
+                        push("dealt", target, source, damage_type.name(), *amount);
+                    }
+                }
+            }
+
+            for (kind, by_target) in [
+                ("hit", &stats.hit_damage_by_target_type),
+                ("crit", &stats.crit_damage_by_target_type),
+                ("weapon_buff", &stats.weapon_buff_damage_by_target_type),
+            ] {
+                for (target, by_type) in sorted(by_target) {
+                    for (damage_type, amount) in sorted(by_type) {
+                        push(kind, target, "Attack", damage_type.name(), *amount);
+                    }
+                }
+            }
+
+            for (damage_type, amount) in sorted(&stats.absorbed_by_type) {
+                push("absorbed", "", "", damage_type.name(), *amount);
+            }
+            // The log doesn't say which damage type resistance or reduction soaked
+            for (kind, amount) in [("absorbed_resistance", stats.absorbed_by_resistance), ("absorbed_reduction", stats.absorbed_by_reduction)] {
+                if amount > 0 {
+                    push(kind, "", "", "", amount);
+                }
+            }
+
+            for (target, amount) in sorted(&stats.healing_by_target_done) {
+                push("healed", target, "", "", *amount);
+            }
+            for (source, amount) in sorted(&stats.healing_by_source_done) {
+                push("healing_source", "", source, "", *amount);
+            }
+        }
+    }
+    out
+}
+
+fn sorted<K: Ord, V>(map: &HashMap<K, V>) -> Vec<(&K, &V)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map(|r| format!("{:.2}", r)).unwrap_or_default()
+}
+
+fn csv_row<I: IntoIterator<Item = String>>(fields: I) -> String {
+    let mut row = fields.into_iter().map(|f| escape_field(&f)).collect::<Vec<_>>().join(",");
+    row.push('\n');
+    row
+}
+
+/// Quote a field if it contains a separator, quote or line break
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
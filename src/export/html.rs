@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::models::{Encounter, CombatantStats, DamageType, DeathRecord, TimelineSeries, ROLLING_DPS_WINDOW, combine_encounter_stats};
+use crate::utils::time::{format_date_time, format_duration};
+
+/// Number of damage dealers drawn in the timeline before the rest are dropped
+const TIMELINE_MAX_LINES: usize = 6;
+
+/// Most points drawn per line; longer fights are averaged into wider buckets
+const TIMELINE_MAX_POINTS: u64 = 640;
+
+/// One timeline line: a run of (seconds of fighting so far, DPS) points per encounter span
+type TimelineRuns = Vec<Vec<(u64, f64)>>;
+
+const TIMELINE_COLORS: [&str; 6] = ["#50c850", "#5aa0ff", "#ffa500", "#dc5adc", "#f0e65a", "#5adcdc"];
+
+const STYLE: &str = "
+body { background: #1b1b1b; color: #dcdcdc; font-family: Segoe UI, Helvetica, Arial, sans-serif; margin: 24px; }
+h1 { font-size: 22px; margin-bottom: 4px; }
+h2 { font-size: 17px; border-bottom: 1px solid #444; padding-bottom: 4px; margin-top: 28px; }
+h3 { font-size: 14px; margin: 12px 0 6px; }
+table { border-collapse: collapse; margin-bottom: 8px; }
+th, td { padding: 3px 10px; text-align: right; white-space: nowrap; }
+th { color: #aaa; font-weight: normal; border-bottom: 1px solid #444; }
+td.name, th.name { text-align: left; }
+tr:nth-child(even) td { background: #232323; }
+.muted { color: #888; font-size: 13px; }
+.bar { display: flex; width: 260px; height: 12px; background: #2c2c2c; border-radius: 2px; overflow: hidden; }
+.bar span { display: block; height: 100%; }
+.legend span { display: inline-block; margin-right: 14px; font-size: 13px; }
+.swatch { display: inline-block; width: 10px; height: 10px; margin-right: 4px; border-radius: 2px; }
+details { margin: 6px 0; background: #222; padding: 6px 10px; border-radius: 4px; }
+summary { cursor: pointer; }
+.columns { display: flex; gap: 32px; flex-wrap: wrap; }
+svg text { fill: #dcdcdc; font-size: 11px; }
+";
+
+/// Render encounters into a single self-contained HTML page.
+///
+/// Several encounters are combined per combatant, the same way the "Encounters"
+/// multi-select view merges them. Everything (styles, charts) is inline.
+pub fn render_html(encounters: &[&Encounter]) -> String {
+    let combined = combine_encounter_stats(encounters.iter().copied());
+    let total_duration: u64 = encounters.iter().map(|e| e.duration()).sum();
+
+    let title = match encounters {
+        [single] => single.get_display_name(),
+        _ => format!("{} encounters combined", encounters.len()),
+    };
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(html, "<title>{}</title>", escape(&title));
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(html, "<h1>{}</h1>", escape(&title));
+
+    let total_damage: u32 = combined.values().map(|s| s.total_damage_dealt).sum();
+    let time_range = match encounters {
+        [single] => single.time_range(),
+        _ => {
+            let start = encounters.iter().min_by_key(|e| e.start_time);
+            let end = encounters.iter().max_by_key(|e| e.end_time);
+            match (start, end) {
+                (Some(start), Some(end)) => format!("{} - {}", format_date_time(start.start_time), format_date_time(end.end_time)),
+                _ => String::new(),
+            }
+        }
+    };
+    let _ = writeln!(html, "<p class=\"muted\">{} &middot; Duration {} &middot; Total damage {}</p>",
+        escape(&time_range), format_duration(total_duration), total_damage);
+
+    if encounters.len() > 1 {
+        html.push_str("<h2>Encounters</h2>\n<table>\n<tr><th class=\"name\">Encounter</th><th>Time</th><th>Duration</th><th>Damage</th></tr>\n");
+        for encounter in encounters {
+            let _ = writeln!(html, "<tr><td class=\"name\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&encounter.get_display_name()), escape(&encounter.time_range()), format_duration(encounter.duration()), encounter.total_damage);
+        }
+        html.push_str("</table>\n");
+    }
+
+    render_damage_done(&mut html, &combined);
+    render_damage_taken(&mut html, &combined);
+    render_healing(&mut html, &combined);
+    render_type_breakdown(&mut html, &combined);
+    render_timeline(&mut html, encounters, &combined);
+    render_deaths(&mut html, encounters);
+    render_details(&mut html, &combined);
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn write_html(encounters: &[&Encounter], path: &Path) -> io::Result<()> {
+    fs::write(path, render_html(encounters))
+}
+
+fn render_damage_done(html: &mut String, stats: &HashMap<String, CombatantStats>) {
+    let rows = sorted_by(stats, |s| s.total_damage_dealt);
+    let total: u32 = rows.iter().map(|(_, s)| s.total_damage_dealt).sum();
+
+    html.push_str("<h2>Damage Done</h2>\n<table>\n<tr><th class=\"name\">Combatant</th><th>Damage</th><th>%</th><th>DPS</th>\
+        <th>Hits</th><th>Crits</th><th>Misses</th><th>Hit%</th><th class=\"name\">By type</th></tr>\n");
+    for (name, s) in rows {
+        let hit_rate = s.hit_rate().map_or_else(|| "-".to_string(), |rate| format!("{:.1}%", rate));
+        let _ = writeln!(html, "<tr><td class=\"name\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"name\">{}</td></tr>",
+            escape(name), s.total_damage_dealt, percent(s.total_damage_dealt, total), rate(s.calculate_dps()),
+            s.hits, s.critical_hits, s.misses, hit_rate, type_bar(&s.damage_by_type_dealt, total));
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_damage_taken(html: &mut String, stats: &HashMap<String, CombatantStats>) {
+    let rows = sorted_by(stats, |s| s.total_damage_received);
+    let total: u32 = rows.iter().map(|(_, s)| s.total_damage_received).sum();
+
+    html.push_str("<h2>Damage Taken</h2>\n<table>\n<tr><th class=\"name\">Combatant</th><th>Damage</th><th>%</th><th>DTPS</th>\
+        <th>Absorbed</th><th class=\"name\">By type</th></tr>\n");
+    for (name, s) in rows {
+        let _ = writeln!(html, "<tr><td class=\"name\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"name\">{}</td></tr>",
+            escape(name), s.total_damage_received, percent(s.total_damage_received, total), rate(s.calculate_dtps()),
+            s.total_damage_absorbed, type_bar(&s.damage_by_type_received, total));
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_healing(html: &mut String, stats: &HashMap<String, CombatantStats>) {
+    let rows = sorted_by(stats, |s| s.healing_done);
+    if rows.is_empty() {
+        return;
+    }
+    let total: u32 = rows.iter().map(|(_, s)| s.healing_done).sum();
+
+    html.push_str("<h2>Healing Done</h2>\n<table>\n<tr><th class=\"name\">Healer</th><th>Healing</th><th>%</th><th>HPS</th>\
+        <th>Effective</th><th>Overheal (est.)</th><th class=\"name\">By spell</th></tr>\n");
+    for (name, s) in rows {
+        let _ = writeln!(html, "<tr><td class=\"name\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"name muted\">{}</td></tr>",
+            escape(name), s.healing_done, percent(s.healing_done, total), rate(s.calculate_hps()),
+            s.effective_healing_done, s.overhealing_done, escape(&format_amounts(&s.healing_by_source_done)));
+    }
+    html.push_str("</table>\n");
+}
+
+/// Pie chart of all damage dealt, by damage type
+fn render_type_breakdown(html: &mut String, stats: &HashMap<String, CombatantStats>) {
+    let mut by_type: HashMap<DamageType, u32> = HashMap::new();
+    for s in stats.values() {
+        for (dtype, amount) in &s.damage_by_type_dealt {
+            *by_type.entry(dtype.clone()).or_default() += amount;
+        }
+    }
+    let total: u32 = by_type.values().sum();
+    if total == 0 {
+        return;
+    }
+    let mut types: Vec<(DamageType, u32)> = by_type.into_iter().collect();
+    types.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    html.push_str("<h2>Damage by Type</h2>\n<div class=\"columns\">\n<svg width=\"180\" height=\"180\" viewBox=\"-90 -90 180 180\">\n");
+    let radius = 80.0;
+    if types.len() == 1 {
+        let _ = writeln!(html, "<circle r=\"{}\" fill=\"{}\"/>", radius, damage_type_color(&types[0].0));
+    } else {
+        let mut angle = -PI / 2.0;
+        for (dtype, amount) in &types {
+            let sweep = *amount as f64 / total as f64 * 2.0 * PI;
+            let (x1, y1) = (radius * angle.cos(), radius * angle.sin());
+            let (x2, y2) = (radius * (angle + sweep).cos(), radius * (angle + sweep).sin());
+            let large_arc = if sweep > PI { 1 } else { 0 };
+            let _ = writeln!(html, "<path d=\"M0,0 L{:.2},{:.2} A{r},{r} 0 {} 1 {:.2},{:.2} Z\" fill=\"{}\"><title>{}: {}</title></path>",
+                x1, y1, large_arc, x2, y2, damage_type_color(dtype), escape(dtype.name()), amount, r = radius);
+            angle += sweep;
+        }
+    }
+    html.push_str("</svg>\n<table>\n<tr><th class=\"name\">Type</th><th>Damage</th><th>%</th></tr>\n");
+    for (dtype, amount) in &types {
+        let _ = writeln!(html, "<tr><td class=\"name\"><span class=\"swatch\" style=\"background:{}\"></span>{}</td><td>{}</td><td>{}</td></tr>",
+            damage_type_color(dtype), escape(dtype.name()), amount, percent(*amount, total));
+    }
+    html.push_str("</table>\n</div>\n");
+}
+
+/// Rolling DPS of the top damage dealers over the fight, one line each.
+/// Encounters are drawn side by side with the time between them left out.
+fn render_timeline(html: &mut String, encounters: &[&Encounter], stats: &HashMap<String, CombatantStats>) {
+    let mut rows: Vec<(&String, &CombatantStats)> = sorted_by(stats, |s| s.total_damage_dealt);
+    rows.retain(|(_, s)| !s.timeline.dealt.is_empty());
+    rows.truncate(TIMELINE_MAX_LINES);
+    let spans = encounter_spans(encounters);
+    if rows.is_empty() || spans.is_empty() {
+        return;
+    }
+    let seconds: u64 = spans.iter().map(|(start, end)| end - start + 1).sum();
+    let bucket = seconds.div_ceil(TIMELINE_MAX_POINTS);
+
+    let series: Vec<(&String, TimelineRuns)> = rows.iter()
+        .map(|(name, s)| {
+            let mut offset = 0;
+            let runs = spans.iter()
+                .map(|&(start, end)| {
+                    let points = s.timeline.bucketed_rolling_dps(TimelineSeries::Dealt, ROLLING_DPS_WINDOW, start, end, bucket)
+                        .into_iter()
+                        .map(|(t, dps)| (offset + t - start, dps))
+                        .collect();
+                    offset += end - start + 1;
+                    points
+                })
+                .collect();
+            (*name, runs)
+        })
+        .collect();
+    let max_value = series.iter()
+        .flat_map(|(_, runs)| runs.iter().flatten().map(|(_, v)| *v))
+        .fold(0.0_f64, f64::max)
+        .max(1.0);
+    let span = (seconds - 1).max(1) as f64;
+
+    let axis_width = 50.0;
+    let chart_width = 640.0;
+    let chart_height = 200.0;
+    let x = |t: u64| axis_width + t as f64 / span * chart_width;
+
+    let mut note = format!("Rolling DPS over a {}s window", ROLLING_DPS_WINDOW);
+    if bucket > 1 {
+        let _ = write!(note, ", averaged per {}s", bucket);
+    }
+    if spans.len() > 1 {
+        note.push_str(" &middot; time between encounters left out");
+    }
+    let _ = writeln!(html, "<h2>DPS Timeline</h2>\n<p class=\"muted\">{}</p>", note);
+    html.push_str("<div class=\"legend\">");
+    for (i, (name, _)) in series.iter().enumerate() {
+        let _ = write!(html, "<span><span class=\"swatch\" style=\"background:{}\"></span>{}</span>", TIMELINE_COLORS[i % TIMELINE_COLORS.len()], escape(name));
+    }
+    html.push_str("</div>\n");
+    let _ = writeln!(html, "<svg width=\"{}\" height=\"{}\">", axis_width + chart_width + 10.0, chart_height + 24.0);
+    let _ = writeln!(html, "<line x1=\"{0}\" y1=\"4\" x2=\"{0}\" y2=\"{1}\" stroke=\"#444\"/><line x1=\"{0}\" y1=\"{1}\" x2=\"{2}\" y2=\"{1}\" stroke=\"#444\"/>",
+        axis_width, chart_height + 4.0, axis_width + chart_width);
+    let mut offset = 0;
+    for (start, end) in &spans[..spans.len() - 1] {
+        offset += end - start + 1;
+        let _ = writeln!(html, "<line x1=\"{0:.1}\" y1=\"4\" x2=\"{0:.1}\" y2=\"{1}\" stroke=\"#444\" stroke-dasharray=\"3,3\"/>",
+            x(offset), chart_height + 4.0);
+    }
+    for (i, (name, runs)) in series.iter().enumerate() {
+        for points in runs {
+            let coords: Vec<String> = points.iter()
+                .map(|(t, v)| format!("{:.1},{:.1}", x(*t), 4.0 + chart_height - v / max_value * chart_height))
+                .collect();
+            let _ = writeln!(html, "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"><title>{}</title></polyline>",
+                TIMELINE_COLORS[i % TIMELINE_COLORS.len()], coords.join(" "), escape(name));
+        }
+    }
+    let _ = writeln!(html, "<text x=\"{}\" y=\"14\" text-anchor=\"end\">{:.0}</text>", axis_width - 6.0, max_value);
+    let _ = writeln!(html, "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">0</text>", axis_width - 6.0, chart_height + 4.0);
+    let _ = writeln!(html, "<text x=\"{}\" y=\"{:.1}\">0s</text>", axis_width, chart_height + 20.0);
+    let _ = writeln!(html, "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>", axis_width + chart_width, chart_height + 20.0, format_duration(seconds - 1));
+    html.push_str("</svg>\n");
+}
+
+/// Seconds each encounter covers on the timeline, in order: from its first to its last damage
+/// (stragglers included) and on until the rolling window has passed it.
+/// Encounters that overlap (after manual splits and merges) share one span.
+fn encounter_spans(encounters: &[&Encounter]) -> Vec<(u64, u64)> {
+    let mut spans: Vec<(u64, u64)> = encounters.iter()
+        .map(|encounter| encounter.stats.values()
+            .filter_map(|s| s.timeline.time_range())
+            .fold((encounter.start_time, encounter.end_time.max(encounter.start_time)), |(start, end), (first, last)| (start.min(first), end.max(last))))
+        .map(|(start, end)| (start, end + ROLLING_DPS_WINDOW - 1))
+        .collect();
+    spans.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Who died, to what, and the damage they took just before
+fn render_deaths(html: &mut String, encounters: &[&Encounter]) {
+    let deaths: Vec<(&Encounter, &DeathRecord)> = encounters.iter()
+        .flat_map(|e| e.deaths.iter().map(move |d| (*e, d)))
+        .collect();
+    if deaths.is_empty() {
+        return;
+    }
+
+    html.push_str("<h2>Deaths</h2>\n");
+    for (encounter, death) in deaths {
+        let _ = writeln!(html, "<details>\n<summary>+{}s &middot; {} <span class=\"muted\">({} damage in the last {} hits)</span></summary>",
+            death.timestamp.saturating_sub(encounter.start_time), escape(&death.summary()), death.recap_damage(), death.recap.len());
+        html.push_str("<table>\n<tr><th>Time</th><th class=\"name\">Attacker</th><th class=\"name\">Source</th><th>Damage</th><th class=\"name\">Types</th></tr>\n");
+        for event in &death.recap {
+            let _ = writeln!(html, "<tr><td>+{}s</td><td class=\"name\">{}</td><td class=\"name\">{}</td><td>{}</td><td class=\"name\">{}</td></tr>",
+                event.timestamp.saturating_sub(encounter.start_time), escape(&event.attacker), escape(&event.source),
+                event.amount, escape(&format_types(&event.damage_types)));
+        }
+        html.push_str("</table>\n</details>\n");
+    }
+}
+
+/// Per-combatant breakdowns, like the player details window
+fn render_details(html: &mut String, stats: &HashMap<String, CombatantStats>) {
+    html.push_str("<h2>Details</h2>\n");
+    let mut names: Vec<&String> = stats.iter()
+        .filter(|(_, s)| s.total_damage_dealt > 0 || s.total_damage_received > 0)
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+
+    for name in names {
+        let s = &stats[name];
+        let _ = writeln!(html, "<details><summary>{} &mdash; {} done, {} taken</summary>",
+            escape(name), s.total_damage_dealt, s.total_damage_received);
+        if let Some(average_bonus) = s.average_attack_bonus() {
+            let _ = writeln!(html, "<p class=\"muted\">Attack bonus avg {:+.1}, max {:+} &middot; natural 1s {} &middot; natural 20s {} &middot; sneak attacks {} ({} dmg) &middot; death attacks {} ({} dmg) &middot; off hand hits {} ({} dmg)</p>",
+                average_bonus, s.max_attack_bonus.unwrap_or(0), s.natural_ones, s.natural_twenties,
+                s.sneak_attacks, s.sneak_attack_damage, s.death_attacks, s.death_attack_damage, s.off_hand_hits, s.off_hand_damage);
+        }
+        html.push_str("<div class=\"columns\">\n");
+
+        html.push_str("<div>\n<h3>Damage Done by Target</h3>\n<table>\n<tr><th class=\"name\">Target / Source</th><th>Damage</th><th class=\"name\">Types</th></tr>\n");
+        for (target, amount) in sorted_amounts(&s.damage_by_target_dealt) {
+            let ac = s.ac_by_target.get(target)
+                .map(|bounds| format!("AC {}", bounds.describe()))
+                .unwrap_or_default();
+            let _ = writeln!(html, "<tr><td class=\"name\"><b>{}</b></td><td><b>{}</b></td><td class=\"name muted\">{}</td></tr>", escape(target), amount, escape(&ac));
+            if let Some(sources) = s.damage_by_target_and_source_dealt.get(target) {
+                for (source, source_amount) in sorted_amounts(sources) {
+                    let types = s.damage_by_target_source_and_type_dealt.get(target)
+                        .and_then(|by_source| by_source.get(source))
+                        .map(format_types)
+                        .unwrap_or_default();
+                    let _ = writeln!(html, "<tr><td class=\"name\">&nbsp;&nbsp;{}</td><td>{}</td><td class=\"name muted\">{}</td></tr>",
+                        escape(source), source_amount, escape(&types));
+                }
+            }
+        }
+        html.push_str("</table>\n</div>\n");
+
+        html.push_str("<div>\n<h3>Damage Taken by Attacker</h3>\n<table>\n<tr><th class=\"name\">Attacker / Source</th><th>Damage</th><th class=\"name\">Types</th></tr>\n");
+        for (attacker, amount) in sorted_amounts(&s.damage_by_attacker_received) {
+            let _ = writeln!(html, "<tr><td class=\"name\"><b>{}</b></td><td><b>{}</b></td><td></td></tr>", escape(attacker), amount);
+            if let Some(sources) = s.damage_by_attacker_and_source_received.get(attacker) {
+                for (source, source_amount) in sorted_amounts(sources) {
+                    let types = s.damage_by_source_and_type_received.get(&format!("{} ({})", attacker, source))
+                        .map(format_types)
+                        .unwrap_or_default();
+                    let _ = writeln!(html, "<tr><td class=\"name\">&nbsp;&nbsp;{}</td><td>{}</td><td class=\"name muted\">{}</td></tr>",
+                        escape(source), source_amount, escape(&types));
+                }
+            }
+        }
+        html.push_str("</table>\n</div>\n</div>\n</details>\n");
+    }
+}
+
+/// Stacked bar of damage types, scaled against `scale_total`
+fn type_bar(by_type: &HashMap<DamageType, u32>, scale_total: u32) -> String {
+    if scale_total == 0 {
+        return String::new();
+    }
+    let mut bar = String::from("<div class=\"bar\">");
+    for (dtype, amount) in sorted_amounts(by_type) {
+        let _ = write!(bar, "<span style=\"width:{:.2}%;background:{}\" title=\"{}: {}\"></span>",
+            *amount as f64 / scale_total as f64 * 100.0, damage_type_color(dtype), escape(dtype.name()), amount);
+    }
+    bar.push_str("</div>");
+    bar
+}
+
+fn format_types(by_type: &HashMap<DamageType, u32>) -> String {
+    sorted_amounts(by_type).iter()
+        .map(|(dtype, amount)| format!("{} {}", amount, dtype))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_amounts(by_name: &HashMap<String, u32>) -> String {
+    sorted_amounts(by_name).iter()
+        .map(|(name, amount)| format!("{} {}", name, amount))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Combatants with a non-zero value, largest first (ties by name)
+fn sorted_by<F: Fn(&CombatantStats) -> u32>(stats: &HashMap<String, CombatantStats>, key: F) -> Vec<(&String, &CombatantStats)> {
+    let mut rows: Vec<_> = stats.iter().filter(|(_, s)| key(s) > 0).collect();
+    rows.sort_by(|a, b| key(b.1).cmp(&key(a.1)).then_with(|| a.0.cmp(b.0)));
+    rows
+}
+
+fn sorted_amounts<K: Ord>(map: &HashMap<K, u32>) -> Vec<(&K, &u32)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    entries
+}
+
+fn percent(amount: u32, total: u32) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", amount as f64 / total as f64 * 100.0)
+    }
+}
+
+fn rate(rate: Option<f64>) -> String {
+    rate.map(|r| format!("{:.1}", r)).unwrap_or_else(|| "-".to_string())
+}
+
+/// Same palette as the logs window uses for damage types
+fn damage_type_color(damage_type: &DamageType) -> String {
+    match damage_type.rgb() {
+        Some([r, g, b]) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        None => "#5f9ea0".to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
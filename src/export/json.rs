@@ -0,0 +1,18 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::models::Encounter;
+
+/// Serialize encounters to pretty-printed JSON.
+///
+/// Goes through `serde_json::Value` so the combatant maps come out with sorted keys
+/// and repeated exports of the same data are byte-identical.
+pub fn encounters_to_json(encounters: &[&Encounter]) -> serde_json::Result<String> {
+    let value = serde_json::to_value(encounters)?;
+    serde_json::to_string_pretty(&value)
+}
+
+pub fn write_json(encounters: &[&Encounter], path: &Path) -> io::Result<()> {
+    let json = encounters_to_json(encounters)?;
+    fs::write(path, json)
+}
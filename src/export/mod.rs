@@ -0,0 +1,61 @@
+pub mod csv;
+pub mod json;
+pub mod html;
+
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::models::Encounter;
+
+pub use self::csv::{write_csv, breakdown_path};
+pub use self::json::write_json;
+pub use self::html::write_html;
+
+/// File formats encounters can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "html" | "htm" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|ext| ext.to_str()).and_then(Self::from_name)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// Export encounters (sorted by id) to `path`. Returns every file that was written.
+pub fn export_encounters(encounters: &[&Encounter], path: &Path, format: ExportFormat) -> io::Result<Vec<PathBuf>> {
+    let mut sorted: Vec<&Encounter> = encounters.to_vec();
+    sorted.sort_by_key(|e| e.id);
+
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    match format {
+        ExportFormat::Csv => write_csv(&sorted, path),
+        ExportFormat::Json => write_json(&sorted, path).map(|_| vec![path.to_path_buf()]),
+        ExportFormat::Html => write_html(&sorted, path).map(|_| vec![path.to_path_buf()]),
+    }
+}
@@ -1,9 +1,17 @@
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use crate::models::{Encounter, CombatantStats, ViewMode, PlayerRegistry, AppSettings, BuffTracker, DamageViewMode, CombatantFilter};
+use crate::export::{export_encounters, ExportFormat};
+use crate::models::{Encounter, EncounterEdits, EncounterHistory, HistoryEntry, CombatantStats, DeathRecord, combine_encounter_stats, ViewMode, PlayerRegistry, AppSettings, BuffTracker, AlertQueue, DamageViewMode, CombatantFilter};
 use crate::gui::helpers::compute_stats_hash;
 use crate::gui::logs_window::LogsWindowState;
-use crate::utils::{load_player_registry, load_app_settings};
+use crate::gui::compare_window::CompareWindowState;
+use crate::log::LogSource;
+use crate::log::finder::collect_log_files;
+use crate::utils::{load_player_registry, load_app_settings, load_encounter_history, load_buff_definitions, load_buff_timers, auto_save_history_entry};
+
+/// Directory (relative to the working directory, like settings.json) that GUI exports go to
+const EXPORT_DIR: &str = "exports";
 
 pub struct NwnLogApp {
     /// All encounters, indexed by encounter ID
@@ -54,6 +62,43 @@ pub struct NwnLogApp {
     pub logs_window_state: LogsWindowState,
     /// Whether the logs window is open
     pub logs_window_open: bool,
+    /// Result of the last encounter export, shown next to the export buttons
+    pub export_status: Option<String>,
+    /// Whether the rolling DPS graph is shown above the combatant list
+    pub show_dps_graph: bool,
+    /// Target profiles window (enemy AC, saves, SR and immunities)
+    pub target_profiles_open: bool,
+    pub target_profile_search: String,
+    /// Manual encounter splits and merges, shared with the log watcher's session
+    pub encounter_edits: Arc<Mutex<EncounterEdits>>,
+    /// Seconds into the selected encounter to split it at
+    pub split_offset: u64,
+    /// Encounters saved from earlier sessions, shared with the log watcher's session
+    pub history: Arc<Mutex<EncounterHistory>>,
+    /// Encounter history browser
+    pub history_open: bool,
+    pub history_search: String,
+    /// Encounters reopened from the history (left out of Overall Stats)
+    pub history_encounter_ids: HashSet<u64>,
+    /// Side-by-side comparison window
+    pub compare_open: bool,
+    pub compare_state: CompareWindowState,
+    /// Buffs and debuffs on every combatant
+    pub effects_open: bool,
+    /// Alerts raised by live log lines and buff timers, shared with the log watcher's session
+    pub alerts: Arc<Mutex<AlertQueue>>,
+    /// Message of the alert flashing on the overlay, and the egui time the flash ends
+    pub alert_flash: Option<(String, f64)>,
+    /// Dry-run result of the log retention settings, shown in the options window
+    pub retention_preview: Option<Vec<String>>,
+    /// Live log or imported old logs, shared with the log watcher thread
+    pub log_source: Arc<Mutex<LogSource>>,
+    /// Files/folders to import (separated by ';') or the log to replay
+    pub import_path_text: String,
+    /// How many times faster than it was written a log is replayed
+    pub replay_speed: f64,
+    /// Result of the last import, shown in the options window
+    pub import_status: Option<String>,
 }
 
 impl NwnLogApp {
@@ -62,6 +107,14 @@ impl NwnLogApp {
         let player_registry = load_player_registry();
         // Load app settings from file
         let settings = load_app_settings();
+        // Load encounters saved by earlier sessions
+        let history = load_encounter_history();
+        // Buff timers from the last run, if they are kept between runs
+        let buff_tracker = if settings.persist_buff_timers {
+            load_buff_timers(load_buff_definitions())
+        } else {
+            BuffTracker::with_definitions(load_buff_definitions())
+        };
 
         Self {
             encounters: Arc::new(Mutex::new(HashMap::new())),
@@ -74,7 +127,7 @@ impl NwnLogApp {
             last_data_hash: 0,
             player_registry: Arc::new(Mutex::new(player_registry)),
             show_options: false,
-            buff_tracker: Arc::new(Mutex::new(BuffTracker::new())),
+            buff_tracker: Arc::new(Mutex::new(buff_tracker)),
             buff_window_spawned: false,
             settings_ref: Some(Arc::new(Mutex::new(settings))),
             damage_view_mode: DamageViewMode::default(),
@@ -88,6 +141,172 @@ impl NwnLogApp {
             log_reload_requested: Arc::new(Mutex::new(false)),
             logs_window_state: LogsWindowState::default(),
             logs_window_open: false,
+            export_status: None,
+            show_dps_graph: false,
+            target_profiles_open: false,
+            target_profile_search: String::new(),
+            encounter_edits: Arc::new(Mutex::new(EncounterEdits::default())),
+            split_offset: 30,
+            history: Arc::new(Mutex::new(history)),
+            history_open: false,
+            history_search: String::new(),
+            history_encounter_ids: HashSet::new(),
+            compare_open: false,
+            compare_state: CompareWindowState::default(),
+            effects_open: false,
+            alerts: Arc::new(Mutex::new(AlertQueue::default())),
+            alert_flash: None,
+            retention_preview: None,
+            log_source: Arc::new(Mutex::new(LogSource::Live)),
+            import_path_text: String::new(),
+            replay_speed: 1.0,
+            import_status: None,
+        }
+    }
+
+    /// Keep the selected encounters (and anything between them) as one encounter, then reparse the log
+    pub fn merge_selected_encounters(&mut self) {
+        let range = {
+            let Ok(encounters) = self.encounters.lock() else { return };
+            let selected: Vec<&Encounter> = self.selected_encounter_ids.iter()
+                .filter_map(|id| encounters.get(id))
+                .collect();
+            let start = selected.iter().map(|e| e.start_time).min();
+            let end = selected.iter().map(|e| e.end_time).max();
+            start.zip(end)
+        };
+        let Some((start, end)) = range else { return };
+        if let Ok(mut edits) = self.encounter_edits.lock() {
+            edits.merge_range(start, end);
+        }
+        self.request_reparse();
+    }
+
+    /// Start a new encounter `split_offset` seconds into the selected one, then reparse the log
+    pub fn split_selected_encounter(&mut self) {
+        let split_time = {
+            let Ok(encounters) = self.encounters.lock() else { return };
+            self.selected_encounter_ids.iter().next()
+                .and_then(|id| encounters.get(id))
+                .map(|e| e.start_time + self.split_offset)
+        };
+        let Some(split_time) = split_time else { return };
+        if let Ok(mut edits) = self.encounter_edits.lock() {
+            edits.split_at(split_time);
+        }
+        self.request_reparse();
+    }
+
+    /// Replace the live log with the old logs in `import_path_text`, read oldest first
+    pub fn import_logs(&mut self) {
+        let paths: Vec<PathBuf> = self.import_path_text.split(';')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .collect();
+        let log_files = match collect_log_files(&paths) {
+            Ok(log_files) if log_files.is_empty() => {
+                self.import_status = Some("No nwclientLog*.txt files found".to_string());
+                return;
+            }
+            Ok(log_files) => log_files,
+            Err(e) => {
+                self.import_status = Some(format!("Import failed: {}", e));
+                return;
+            }
+        };
+
+        self.import_status = Some(format!("Imported {} log file(s)", log_files.len()));
+        if let Ok(mut source) = self.log_source.lock() {
+            *source = LogSource::Archive(log_files);
+        }
+        self.request_reparse();
+    }
+
+    /// Feed the log in `import_path_text` through the live pipeline at `replay_speed`
+    pub fn replay_log(&mut self) {
+        let path = PathBuf::from(self.import_path_text.trim());
+        if !path.is_file() {
+            self.import_status = Some(format!("Replay needs a single log file, not '{}'", path.display()));
+            return;
+        }
+
+        self.import_status = Some(format!("Replaying {} at {}x", path.display(), self.replay_speed));
+        if let Ok(mut source) = self.log_source.lock() {
+            *source = LogSource::Replay { path, speed: self.replay_speed };
+        }
+        self.request_reparse();
+    }
+
+    /// Stop showing imported or replayed logs and follow the newest log again
+    pub fn return_to_live_log(&mut self) {
+        self.import_status = None;
+        if let Ok(mut source) = self.log_source.lock() {
+            *source = LogSource::Live;
+        }
+        self.request_reparse();
+    }
+
+    /// Whether imported or replayed logs are shown instead of the live log
+    pub fn is_showing_import(&self) -> bool {
+        self.log_source.lock().is_ok_and(|source| *source != LogSource::Live)
+    }
+
+    /// Encounter IDs change when the log is parsed again, so the selection is dropped
+    pub fn request_reparse(&mut self) {
+        self.selected_encounter_ids.clear();
+        if let Ok(mut reload_flag) = self.log_reload_requested.lock() {
+            *reload_flag = true;
+        }
+    }
+
+    /// Add saved encounters to the encounter list (under new IDs) and select them
+    pub fn open_history_entries(&mut self, entries: Vec<HistoryEntry>) {
+        if entries.is_empty() {
+            return;
+        }
+        let (Ok(mut encounters), Ok(mut counter)) = (self.encounters.lock(), self.encounter_counter.lock()) else {
+            return;
+        };
+        for entry in entries {
+            let mut encounter = entry.encounter;
+            encounter.id = *counter;
+            *counter += 1;
+            self.history_encounter_ids.insert(encounter.id);
+            self.selected_encounter_ids.insert(encounter.id);
+            encounters.insert(encounter.id, encounter);
+        }
+        self.view_mode = ViewMode::MultipleSelected;
+    }
+
+    /// Open the comparison window, comparing the two selected encounters if exactly two are selected
+    pub fn open_comparison(&mut self) {
+        let mut selected: Vec<u64> = self.selected_encounter_ids.iter().copied().collect();
+        selected.sort_unstable();
+        if let [left, right] = selected[..]
+            && let Ok(encounters) = self.encounters.lock()
+        {
+            self.compare_state.set_encounters(&encounters, left, right);
+        }
+        self.compare_open = true;
+    }
+
+    /// Save the fight in progress, which is otherwise only saved once the next one starts
+    pub fn save_current_encounter_to_history(&self) {
+        let Some(current_id) = self.current_encounter_id.lock().ok().and_then(|id| *id) else {
+            return;
+        };
+        let Some(mut encounter) = self.encounters.lock().ok().and_then(|e| e.get(&current_id).cloned()) else {
+            return;
+        };
+        if encounter.stats.values().all(|stats| stats.total_damage_dealt == 0) {
+            return;
+        }
+        encounter.update_most_damaged();
+        if let Ok(mut history) = self.history.lock()
+            && let Some(log_file) = history.active_log.clone()
+        {
+            auto_save_history_entry(&mut history, HistoryEntry { log_file, encounter });
         }
     }
 
@@ -123,6 +342,27 @@ impl NwnLogApp {
         }
     }
 
+    /// Deaths in the encounters shown by the current view, with each encounter's start time
+    pub fn get_current_deaths(&self) -> Vec<(u64, DeathRecord)> {
+        let Ok(encounters) = self.encounters.try_lock() else {
+            return Vec::new();
+        };
+        let shown: Vec<&Encounter> = if !self.selected_encounter_ids.is_empty() || self.view_mode == ViewMode::MultipleSelected {
+            self.selected_encounter_ids.iter().filter_map(|id| encounters.get(id)).collect()
+        } else if self.view_mode == ViewMode::OverallStats {
+            encounters.values().filter(|e| !self.history_encounter_ids.contains(&e.id)).collect()
+        } else {
+            let current_id = self.current_encounter_id.try_lock().ok().and_then(|id| *id);
+            current_id.and_then(|id| encounters.get(&id)).into_iter().collect()
+        };
+
+        let mut deaths: Vec<(u64, DeathRecord)> = shown.iter()
+            .flat_map(|e| e.deaths.iter().map(|d| (e.start_time, d.clone())))
+            .collect();
+        deaths.sort_by_key(|(_, d)| d.timestamp);
+        deaths
+    }
+
     fn get_combined_selected_stats_safe(&self) -> HashMap<String, CombatantStats> {
         if let Ok(encounters) = self.encounters.try_lock() {
             self.combine_selected_encounters_stats(&encounters)
@@ -137,20 +377,8 @@ impl NwnLogApp {
     }
 
     fn combine_selected_encounters_stats(&self, encounters: &HashMap<u64, Encounter>) -> HashMap<String, CombatantStats> {
-        let mut combined_stats = HashMap::new();
-        
         // Combine stats from all selected encounters
-        for encounter_id in &self.selected_encounter_ids {
-            if let Some(encounter) = encounters.get(encounter_id) {
-                for (name, stats) in &encounter.stats {
-                    let combined = combined_stats.entry(name.clone()).or_insert_with(CombatantStats::default);
-                    
-                    self.aggregate_stats(combined, stats);
-                }
-            }
-        }
-        
-        combined_stats
+        combine_encounter_stats(self.selected_encounter_ids.iter().filter_map(|id| encounters.get(id)))
     }
 
     fn get_overall_stats_safe(&self) -> HashMap<String, CombatantStats> {
@@ -167,158 +395,9 @@ impl NwnLogApp {
     }
 
     fn combine_all_encounters_stats(&self, encounters: &HashMap<u64, Encounter>) -> HashMap<String, CombatantStats> {
-        let mut overall_stats = HashMap::new();
-        
-        for encounter in encounters.values() {
-            for (name, stats) in &encounter.stats {
-                let overall = overall_stats.entry(name.clone()).or_insert_with(CombatantStats::default);
-                
-                self.aggregate_stats(overall, stats);
-            }
-        }
-        
-        overall_stats
-    }
-    
-    fn aggregate_stats(&self, target: &mut CombatantStats, source: &CombatantStats) {
-        // Aggregate all stats
-        target.hits += source.hits;
-        target.misses += source.misses;
-        target.critical_hits += source.critical_hits;
-        target.weapon_buffs += source.weapon_buffs;
-        target.total_damage_dealt += source.total_damage_dealt;
-        target.hit_damage += source.hit_damage;
-        target.crit_damage += source.crit_damage;
-        target.weapon_buff_damage += source.weapon_buff_damage;
-        target.times_attacked += source.times_attacked;
-        target.total_damage_received += source.total_damage_received;
-        target.total_damage_absorbed += source.total_damage_absorbed;
-        
-        // Aggregate damage by type dealt
-        for (dtype, amount) in &source.damage_by_type_dealt {
-            *target.damage_by_type_dealt.entry(dtype.clone()).or_default() += *amount;
-        }
-        
-        // Aggregate hit damage by type
-        for (dtype, amount) in &source.hit_damage_by_type {
-            *target.hit_damage_by_type.entry(dtype.clone()).or_default() += *amount;
-        }
-        
-        // Aggregate crit damage by type
-        for (dtype, amount) in &source.crit_damage_by_type {
-            *target.crit_damage_by_type.entry(dtype.clone()).or_default() += *amount;
-        }
-        
-        // Aggregate weapon buff damage by type
-        for (dtype, amount) in &source.weapon_buff_damage_by_type {
-            *target.weapon_buff_damage_by_type.entry(dtype.clone()).or_default() += *amount;
-        }
-        
-        // Aggregate damage sources
-        for (source_name, amount) in &source.damage_by_source_dealt {
-            *target.damage_by_source_dealt.entry(source_name.clone()).or_default() += *amount;
-        }
-        
-        // Aggregate damage by source and type dealt
-        for (source_name, type_map) in &source.damage_by_source_and_type_dealt {
-            let target_type_map = target.damage_by_source_and_type_dealt.entry(source_name.clone()).or_default();
-            for (dtype, amount) in type_map {
-                *target_type_map.entry(dtype.clone()).or_default() += *amount;
-            }
-        }
-        
-        // Aggregate damage by target
-        for (target_name, amount) in &source.damage_by_target_dealt {
-            *target.damage_by_target_dealt.entry(target_name.clone()).or_default() += *amount;
-        }
-        
-        // Aggregate damage by target and source dealt
-        for (target_name, source_map) in &source.damage_by_target_and_source_dealt {
-            let target_map = target.damage_by_target_and_source_dealt.entry(target_name.clone()).or_default();
-            for (source_name, amount) in source_map {
-                *target_map.entry(source_name.clone()).or_default() += *amount;
-            }
-        }
-        
-        // Aggregate damage by target, source, and type dealt
-        for (target_name, source_map) in &source.damage_by_target_source_and_type_dealt {
-            let target_map = target.damage_by_target_source_and_type_dealt.entry(target_name.clone()).or_default();
-            for (source_name, type_map) in source_map {
-                let source_map = target_map.entry(source_name.clone()).or_default();
-                for (dtype, amount) in type_map {
-                    *source_map.entry(dtype.clone()).or_default() += *amount;
-                }
-            }
-        }
-        
-        // Aggregate hit damage by target and type
-        for (target_name, type_map) in &source.hit_damage_by_target_type {
-            let target_map = target.hit_damage_by_target_type.entry(target_name.clone()).or_default();
-            for (dtype, amount) in type_map {
-                *target_map.entry(dtype.clone()).or_default() += *amount;
-            }
-        }
-        
-        // Aggregate crit damage by target and type
-        for (target_name, type_map) in &source.crit_damage_by_target_type {
-            let target_map = target.crit_damage_by_target_type.entry(target_name.clone()).or_default();
-            for (dtype, amount) in type_map {
-                *target_map.entry(dtype.clone()).or_default() += *amount;
-            }
-        }
-        
-        // Aggregate weapon buff damage by target and type
-        for (target_name, type_map) in &source.weapon_buff_damage_by_target_type {
-            let target_map = target.weapon_buff_damage_by_target_type.entry(target_name.clone()).or_default();
-            for (dtype, amount) in type_map {
-                *target_map.entry(dtype.clone()).or_default() += *amount;
-            }
-        }
-        
-        // Aggregate damage sources received
-        for (source_name, amount) in &source.damage_by_source_received {
-            *target.damage_by_source_received.entry(source_name.clone()).or_default() += *amount;
-        }
-        
-        // Aggregate damage by source and type received
-        for (source_name, type_map) in &source.damage_by_source_and_type_received {
-            let target_source_map = target.damage_by_source_and_type_received.entry(source_name.clone()).or_default();
-            for (dtype, amount) in type_map {
-                *target_source_map.entry(dtype.clone()).or_default() += *amount;
-            }
-        }
-        
-        // Aggregate damage by attacker received
-        for (attacker, amount) in &source.damage_by_attacker_received {
-            *target.damage_by_attacker_received.entry(attacker.clone()).or_default() += *amount;
-        }
-        
-        // Aggregate damage by attacker and source received
-        for (attacker, source_map) in &source.damage_by_attacker_and_source_received {
-            let target_attacker_map = target.damage_by_attacker_and_source_received.entry(attacker.clone()).or_default();
-            for (source_name, amount) in source_map {
-                *target_attacker_map.entry(source_name.clone()).or_default() += *amount;
-            }
-        }
-        
-        // Aggregate absorbed damage by type
-        for (dtype, amount) in &source.absorbed_by_type {
-            *target.absorbed_by_type.entry(dtype.clone()).or_default() += *amount;
-        }
-        
-        // Update timing for combined stats
-        if let Some(first) = source.first_action_time {
-            target.first_action_time = Some(
-                target.first_action_time.map_or(first, |existing| existing.min(first))
-            );
-        }
-        if let Some(last) = source.last_action_time {
-            target.last_action_time = Some(
-                target.last_action_time.map_or(last, |existing| existing.max(last))
-            );
-        }
+        combine_encounter_stats(encounters.values().filter(|e| !self.history_encounter_ids.contains(&e.id)))
     }
-    
+
     pub fn update_sorted_cache(&mut self, stats_map: &HashMap<String, CombatantStats>) {
         let current_hash = compute_stats_hash(stats_map);
 
@@ -335,17 +414,17 @@ impl NwnLogApp {
                     match self.combatant_filter {
                         crate::models::CombatantFilter::All => true,
                         crate::models::CombatantFilter::Friendlies => {
-                            // Check if this is a known player
+                            // Check if this is a known player or one of their pets
                             if let Ok(registry) = self.player_registry.lock() {
-                                registry.is_player(name)
+                                registry.is_friendly(name)
                             } else {
                                 false
                             }
                         },
                         crate::models::CombatantFilter::Enemies => {
-                            // Check if this is NOT a known player
+                            // Check if this is NOT a known player or pet
                             if let Ok(registry) = self.player_registry.lock() {
-                                !registry.is_player(name)
+                                !registry.is_friendly(name)
                             } else {
                                 true
                             }
@@ -375,6 +454,13 @@ impl NwnLogApp {
                             .then(a.0.cmp(&b.0))
                     });
                 }
+                crate::models::DamageViewMode::Healing => {
+                    self.cached_sorted_combatants.sort_by(|a, b| {
+                        b.1.healing_done.cmp(&a.1.healing_done)
+                            .then(b.1.healing_received.cmp(&a.1.healing_received))
+                            .then(a.0.cmp(&b.0))
+                    });
+                }
             }
 
             self.last_data_hash = current_hash;
@@ -383,6 +469,36 @@ impl NwnLogApp {
         }
     }
 
+    /// Export the selected encounters (or all of them if none are selected) into `exports/`
+    pub fn export_encounters(&mut self, format: ExportFormat) {
+        let result = if let Ok(encounters) = self.encounters.lock() {
+            let selected: Vec<&Encounter> = encounters.values()
+                .filter(|e| self.selected_encounter_ids.is_empty() || self.selected_encounter_ids.contains(&e.id))
+                .collect();
+
+            if selected.is_empty() {
+                Err("No encounters to export".to_string())
+            } else {
+                let secs = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let path = PathBuf::from(EXPORT_DIR).join(format!("encounters_{}.{}", secs, format.extension()));
+                export_encounters(&selected, &path, format)
+                    .map(|_| format!("Exported {} encounter(s) to {}", selected.len(), path.display()))
+                    .map_err(|e| format!("Export failed: {}", e))
+            }
+        } else {
+            Err("Export failed: encounter data is unavailable".to_string())
+        };
+
+        match &result {
+            Ok(msg) => eprintln!("{}", msg),
+            Err(msg) => eprintln!("{}", msg),
+        }
+        self.export_status = Some(result.unwrap_or_else(|e| e));
+    }
+
     /// Format damage stats for copying to clipboard
     pub fn format_damage_for_copy(&self) -> String {
         use crate::models::DamageViewMode;
@@ -390,6 +506,7 @@ impl NwnLogApp {
         let header = match self.damage_view_mode {
             DamageViewMode::DamageDone => " Damage Done ",
             DamageViewMode::DamageTaken => " Damage Taken ",
+            DamageViewMode::Healing => " Healing Done ",
         };
 
         let mut lines = vec![header.to_string()];
@@ -399,6 +516,7 @@ impl NwnLogApp {
             .map(|(_, s)| match self.damage_view_mode {
                 DamageViewMode::DamageDone => s.total_damage_dealt,
                 DamageViewMode::DamageTaken => s.total_damage_received,
+                DamageViewMode::Healing => s.healing_done,
             }).sum();
 
         // Find max damage value to determine alignment width
@@ -406,6 +524,7 @@ impl NwnLogApp {
             .map(|(_, s)| match self.damage_view_mode {
                 DamageViewMode::DamageDone => s.total_damage_dealt,
                 DamageViewMode::DamageTaken => s.total_damage_received,
+                DamageViewMode::Healing => s.healing_done,
             }).max().unwrap_or(0);
 
         let damage_width = max_damage.to_string().len().max(4); // At least 4 chars
@@ -414,9 +533,15 @@ impl NwnLogApp {
             let damage = match self.damage_view_mode {
                 DamageViewMode::DamageDone => stats.total_damage_dealt,
                 DamageViewMode::DamageTaken => stats.total_damage_received,
+                DamageViewMode::Healing => stats.healing_done,
             };
 
-            let dps = stats.calculate_dps().map(|d| d.round() as u32).unwrap_or(0);
+            let rate = if self.damage_view_mode == DamageViewMode::Healing {
+                stats.calculate_hps()
+            } else {
+                stats.calculate_dps()
+            };
+            let dps = rate.map(|d| d.round() as u32).unwrap_or(0);
             let percentage = if total_damage > 0 && damage > 0 {
                 (damage as f32 / total_damage as f32 * 100.0).round() as u32
             } else {
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use eframe::egui;
+use crate::models::{Encounter, compare_combatants};
+use crate::utils::time::format_clock_time;
+
+/// One side of a comparison: a combatant in an encounter
+#[derive(Debug, Clone, Default)]
+pub struct CompareSide {
+    pub encounter_id: Option<u64>,
+    pub combatant: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompareWindowState {
+    pub left: CompareSide,
+    pub right: CompareSide,
+}
+
+impl CompareWindowState {
+    /// Compare two encounters, picking the same combatant on both sides where possible
+    pub fn set_encounters(&mut self, encounters: &HashMap<u64, Encounter>, left: u64, right: u64) {
+        self.left.encounter_id = Some(left);
+        self.right.encounter_id = Some(right);
+        let shared = self.left.combatant.clone()
+            .filter(|name| [left, right].iter().all(|id| has_combatant(encounters, *id, name)));
+        match shared {
+            Some(name) => self.right.combatant = Some(name),
+            None => {
+                self.left.combatant = top_damage_dealer(encounters, left);
+                self.right.combatant = self.left.combatant.clone()
+                    .filter(|name| has_combatant(encounters, right, name))
+                    .or_else(|| top_damage_dealer(encounters, right));
+            }
+        }
+    }
+}
+
+/// Side-by-side stats for two combatants/encounters, with deltas (right minus left)
+pub fn show_compare_window(
+    ctx: &egui::Context,
+    encounters: Arc<Mutex<HashMap<u64, Encounter>>>,
+    state: &mut CompareWindowState,
+    is_open: &mut bool
+) {
+    if !*is_open {
+        return;
+    }
+
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of("compare_window"),
+        egui::ViewportBuilder::default()
+            .with_inner_size([560.0, 480.0])
+            .with_min_inner_size([400.0, 250.0])
+            .with_resizable(true)
+            .with_decorations(false)  // Remove system decorations for custom title bar
+            .with_always_on_top()
+            .with_title("Compare"),
+        |ctx, class| {
+            assert!(class == egui::ViewportClass::Immediate);
+            ctx.set_visuals(egui::Visuals::dark());
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                // Custom header bar
+                let header_rect = ui.allocate_space(egui::Vec2::new(ui.available_width(), 35.0)).1;
+
+                // Make the header draggable except for the X button area
+                let draggable_rect = egui::Rect::from_min_size(
+                    header_rect.min,
+                    egui::Vec2::new(header_rect.width() - 30.0, header_rect.height())
+                );
+                let drag_response = ui.allocate_rect(draggable_rect, egui::Sense::click_and_drag());
+                if drag_response.drag_started() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                }
+
+                ui.scope_builder(egui::UiBuilder::new().max_rect(header_rect), |ui| {
+                    ui.horizontal(|ui| {
+                        let title_pos = egui::Pos2::new(header_rect.min.x + 15.0, header_rect.center().y);
+                        ui.painter().text(title_pos, egui::Align2::LEFT_CENTER, "Compare",
+                            egui::FontId::proportional(16.0), ui.visuals().text_color());
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            // Close button (X)
+                            if ui.add(egui::Button::new(egui::RichText::new("X").size(12.0))
+                                .min_size(egui::Vec2::new(25.0, 25.0))).clicked() {
+                                *is_open = false;
+                            }
+                        });
+                    });
+                });
+
+                ui.separator();
+
+                let Ok(encounters) = encounters.try_lock() else {
+                    return;
+                };
+                let mut sorted: Vec<&Encounter> = encounters.values().collect();
+                sorted.sort_by_key(|e| std::cmp::Reverse(e.start_time));
+
+                show_side_picker(ui, "A", &mut state.left, &sorted);
+                show_side_picker(ui, "B", &mut state.right, &sorted);
+                ui.separator();
+
+                let stats = |side: &CompareSide| {
+                    let encounter = encounters.get(&side.encounter_id?)?;
+                    encounter.stats.get(side.combatant.as_ref()?)
+                };
+                let (Some(left), Some(right)) = (stats(&state.left), stats(&state.right)) else {
+                    ui.label("Pick an encounter and a combatant for both sides");
+                    return;
+                };
+
+                let positive = egui::Color32::from_rgb(100, 200, 100);
+                let negative = egui::Color32::from_rgb(220, 100, 100);
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        egui::Grid::new("compare_grid")
+                            .striped(true)
+                            .num_columns(4)
+                            .show(ui, |ui| {
+                                ui.strong("");
+                                ui.strong("A");
+                                ui.strong("B");
+                                ui.strong("B - A");
+                                ui.end_row();
+
+                                for row in compare_combatants(left, right) {
+                                    ui.label(&row.label);
+                                    ui.label(row.format_value(row.left));
+                                    ui.label(row.format_value(row.right));
+                                    let color = match row.delta() {
+                                        Some(delta) if delta > 0.0 => positive,
+                                        Some(delta) if delta < 0.0 => negative,
+                                        _ => ui.visuals().text_color(),
+                                    };
+                                    ui.label(egui::RichText::new(row.format_delta()).color(color));
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        },
+    );
+}
+
+fn show_side_picker(ui: &mut egui::Ui, label: &str, side: &mut CompareSide, encounters: &[&Encounter]) {
+    let encounter_label = |encounter: &Encounter| format!("{} {}", format_clock_time(encounter.start_time), encounter.get_display_name());
+    let selected = side.encounter_id.and_then(|id| encounters.iter().find(|e| e.id == id));
+
+    ui.horizontal(|ui| {
+        ui.strong(format!("{}:", label));
+        egui::ComboBox::from_id_salt(("compare_encounter", label))
+            .selected_text(selected.map(|e| encounter_label(e)).unwrap_or_else(|| "Encounter".to_string()))
+            .width(220.0)
+            .show_ui(ui, |ui| {
+                for encounter in encounters {
+                    ui.selectable_value(&mut side.encounter_id, Some(encounter.id), encounter_label(encounter));
+                }
+            });
+
+        let mut combatants: Vec<(&String, u32)> = selected
+            .map(|e| e.stats.iter().map(|(name, stats)| (name, stats.total_damage_dealt)).collect())
+            .unwrap_or_default();
+        combatants.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        egui::ComboBox::from_id_salt(("compare_combatant", label))
+            .selected_text(side.combatant.clone().unwrap_or_else(|| "Combatant".to_string()))
+            .width(160.0)
+            .show_ui(ui, |ui| {
+                for (name, _) in combatants {
+                    ui.selectable_value(&mut side.combatant, Some(name.clone()), name.as_str());
+                }
+            });
+    });
+}
+
+fn has_combatant(encounters: &HashMap<u64, Encounter>, encounter_id: u64, name: &str) -> bool {
+    encounters.get(&encounter_id).is_some_and(|e| e.stats.contains_key(name))
+}
+
+fn top_damage_dealer(encounters: &HashMap<u64, Encounter>, encounter_id: u64) -> Option<String> {
+    encounters.get(&encounter_id)?.stats.iter()
+        .max_by_key(|(_, stats)| stats.total_damage_dealt)
+        .map(|(name, _)| name.clone())
+}
@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+use eframe::egui;
+use crate::models::{ActiveEffect, AppSettings, BuffTracker, EffectKind, PlayerRegistry};
+
+/// Buffs cast by the party (listed by caster) and debuffs on enemies, with the time each has left
+pub fn show_effects_window(
+    ctx: &egui::Context,
+    buff_tracker: Arc<Mutex<BuffTracker>>,
+    player_registry: Arc<Mutex<PlayerRegistry>>,
+    settings: Arc<Mutex<AppSettings>>,
+    is_open: &mut bool
+) {
+    if !*is_open {
+        return;
+    }
+
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of("effects_window"),
+        egui::ViewportBuilder::default()
+            .with_inner_size([360.0, 420.0])
+            .with_min_inner_size([250.0, 150.0])
+            .with_resizable(true)
+            .with_decorations(false)  // Remove system decorations for custom title bar
+            .with_always_on_top()
+            .with_title("Effects"),
+        |ctx, class| {
+            assert!(class == egui::ViewportClass::Immediate);
+            ctx.set_visuals(egui::Visuals::dark());
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                // Custom header bar
+                let header_rect = ui.allocate_space(egui::Vec2::new(ui.available_width(), 35.0)).1;
+
+                // Make the header draggable except for the X button area
+                let draggable_rect = egui::Rect::from_min_size(
+                    header_rect.min,
+                    egui::Vec2::new(header_rect.width() - 30.0, header_rect.height())
+                );
+                let drag_response = ui.allocate_rect(draggable_rect, egui::Sense::click_and_drag());
+                if drag_response.drag_started() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                }
+
+                ui.scope_builder(egui::UiBuilder::new().max_rect(header_rect), |ui| {
+                    ui.horizontal(|ui| {
+                        let title_pos = egui::Pos2::new(header_rect.min.x + 15.0, header_rect.center().y);
+                        ui.painter().text(title_pos, egui::Align2::LEFT_CENTER, "Effects",
+                            egui::FontId::proportional(16.0), ui.visuals().text_color());
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            // Close button (X)
+                            if ui.add(egui::Button::new(egui::RichText::new("X").size(12.0))
+                                .min_size(egui::Vec2::new(25.0, 25.0))).clicked() {
+                                *is_open = false;
+                            }
+                        });
+                    });
+                });
+
+                ui.separator();
+
+                let warning_seconds = settings.lock().map(|settings| settings.buff_warning_seconds as i64).unwrap_or(0);
+                let Ok(mut tracker) = buff_tracker.lock() else {
+                    return;
+                };
+                tracker.effects.remove_stale();
+                if tracker.effects.is_empty() {
+                    ui.label("No buffs or debuffs tracked yet");
+                    return;
+                }
+
+                let (party, enemies): (Vec<_>, Vec<_>) = {
+                    let Ok(registry) = player_registry.lock() else {
+                        return;
+                    };
+                    tracker.effects.by_target().into_iter()
+                        .partition(|(target, _)| registry.is_friendly(target))
+                };
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        if !party.is_empty() {
+                            ui.heading("Buffs cast by the party");
+                            // Buffs are kept on their caster, so don't present them as what each member has on
+                            ui.weak("The log doesn't say who a buff was cast on, so each buff is listed under its caster");
+                        }
+                        for (heading, targets) in [(None, party), (Some("Enemies"), enemies)] {
+                            if targets.is_empty() {
+                                continue;
+                            }
+                            if let Some(heading) = heading {
+                                ui.heading(heading);
+                            }
+                            for (target, effects) in targets {
+                                ui.strong(target);
+                                for effect in effects {
+                                    show_effect_row(ui, effect, warning_seconds);
+                                }
+                                ui.add_space(4.0);
+                            }
+                        }
+                    });
+            });
+
+            // Keep the countdowns ticking
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        },
+    );
+}
+
+fn show_effect_row(ui: &mut egui::Ui, effect: &ActiveEffect, warning_seconds: i64) {
+    let remaining = effect.remaining_seconds();
+    let (time_text, color) = if effect.is_expired() {
+        let expired = match effect.kind {
+            EffectKind::Buff => "expired - recast",
+            EffectKind::Debuff => "expired",
+        };
+        (expired.to_string(), egui::Color32::from_rgb(220, 80, 80))
+    } else if remaining <= warning_seconds {
+        (format_remaining(remaining), egui::Color32::from_rgb(255, 165, 0))
+    } else {
+        (format_remaining(remaining), ui.visuals().text_color())
+    };
+
+    ui.horizontal(|ui| {
+        ui.add_space(12.0);
+        ui.label(&effect.name);
+        if effect.caster != effect.target {
+            ui.weak(format!("from {}", effect.caster));
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label(egui::RichText::new(time_text).color(color));
+        });
+    });
+}
+
+fn format_remaining(seconds: i64) -> String {
+    if seconds >= 60 {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
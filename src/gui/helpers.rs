@@ -19,6 +19,8 @@ pub fn compute_stats_hash(stats_map: &HashMap<String, CombatantStats>) -> u64 {
         stats.misses.hash(&mut hasher);
         stats.critical_hits.hash(&mut hasher);
         stats.times_attacked.hash(&mut hasher);
+        stats.healing_done.hash(&mut hasher);
+        stats.healing_received.hash(&mut hasher);
     }
     
     hasher.finish()
@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex};
+use eframe::egui;
+use crate::models::{EncounterHistory, HistoryEntry};
+use crate::utils::time::{format_clock_time, format_date_time};
+
+/// Browse encounters saved from earlier sessions. Returns the entries the user chose to open.
+pub fn show_history_window(
+    ctx: &egui::Context,
+    history: Arc<Mutex<EncounterHistory>>,
+    search_text: &mut String,
+    is_open: &mut bool
+) -> Vec<HistoryEntry> {
+    let mut to_open = Vec::new();
+    if !*is_open {
+        return to_open;
+    }
+
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of("history_window"),
+        egui::ViewportBuilder::default()
+            .with_inner_size([520.0, 480.0])
+            .with_min_inner_size([350.0, 250.0])
+            .with_resizable(true)
+            .with_decorations(false)  // Remove system decorations for custom title bar
+            .with_always_on_top()
+            .with_title("Encounter History"),
+        |ctx, class| {
+            assert!(class == egui::ViewportClass::Immediate);
+            ctx.set_visuals(egui::Visuals::dark());
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                // Custom header bar
+                let header_rect = ui.allocate_space(egui::Vec2::new(ui.available_width(), 35.0)).1;
+
+                // Make the header draggable except for the X button area
+                let draggable_rect = egui::Rect::from_min_size(
+                    header_rect.min,
+                    egui::Vec2::new(header_rect.width() - 30.0, header_rect.height())
+                );
+                let drag_response = ui.allocate_rect(draggable_rect, egui::Sense::click_and_drag());
+                if drag_response.drag_started() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                }
+
+                ui.scope_builder(egui::UiBuilder::new().max_rect(header_rect), |ui| {
+                    ui.horizontal(|ui| {
+                        let title_pos = egui::Pos2::new(header_rect.min.x + 15.0, header_rect.center().y);
+                        ui.painter().text(title_pos, egui::Align2::LEFT_CENTER, "Encounter History",
+                            egui::FontId::proportional(16.0), ui.visuals().text_color());
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            // Close button (X)
+                            if ui.add(egui::Button::new(egui::RichText::new("X").size(12.0))
+                                .min_size(egui::Vec2::new(25.0, 25.0))).clicked() {
+                                *is_open = false;
+                            }
+                        });
+                    });
+                });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    ui.add(egui::TextEdit::singleline(search_text).desired_width(200.0))
+                        .on_hover_text("Combatant name, log file or date (YYYY-MM-DD)");
+                });
+                ui.add_space(4.0);
+
+                let Ok(history) = history.try_lock() else {
+                    ui.label("Loading...");
+                    return;
+                };
+                let search = search_text.to_lowercase();
+                let entries: Vec<&HistoryEntry> = history.entries()
+                    .filter(|entry| search.is_empty() || matches_search(entry, &search))
+                    .collect();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        if entries.is_empty() {
+                            ui.label("No saved encounters yet");
+                        }
+
+                        // Newest first, with a heading for each day
+                        let mut current_day = String::new();
+                        for entry in entries {
+                            let encounter = &entry.encounter;
+                            let day = format_date_time(encounter.start_time).chars().take(10).collect::<String>();
+                            if day != current_day {
+                                ui.add_space(4.0);
+                                ui.label(egui::RichText::new(&day).strong());
+                                current_day = day;
+                            }
+
+                            ui.horizontal(|ui| {
+                                if ui.small_button("Open").clicked() {
+                                    to_open.push(entry.clone());
+                                }
+                                ui.label(format!("{} {}", format_clock_time(encounter.start_time), encounter.get_display_name()));
+                                ui.label(egui::RichText::new(format!("{} dmg · {}", encounter.total_damage, entry.log_file))
+                                    .color(egui::Color32::from_gray(150)));
+                            });
+                        }
+                    });
+            });
+        },
+    );
+
+    to_open
+}
+
+fn matches_search(entry: &HistoryEntry, search: &str) -> bool {
+    entry.log_file.to_lowercase().contains(search)
+        || format_date_time(entry.encounter.start_time).contains(search)
+        || entry.encounter.stats.keys().any(|name| name.to_lowercase().contains(search))
+}
@@ -4,8 +4,17 @@ pub mod helpers;
 pub mod buff_window;
 pub mod player_details_window;
 pub mod logs_window;
+pub mod timeline_plot;
+pub mod target_profile_window;
+pub mod history_window;
+pub mod compare_window;
+pub mod effects_window;
 
 pub use app::NwnLogApp;
 pub use buff_window::show_buff_window;
 pub use player_details_window::show_player_details_window;
-pub use logs_window::{show_logs_window, LogsWindowState};
\ No newline at end of file
+pub use logs_window::{show_logs_window, LogsWindowState};
+pub use target_profile_window::show_target_profile_window;
+pub use history_window::show_history_window;
+pub use compare_window::{show_compare_window, CompareWindowState};
+pub use effects_window::show_effects_window;
\ No newline at end of file
@@ -1,6 +1,7 @@
 use std::sync::{Arc, Mutex};
 use eframe::egui;
-use crate::models::{CombatantStats, PlayerRegistry};
+use crate::models::{CombatantStats, CheckStats, DamageType, PlayerRegistry, SaveSummary, ROLLING_DPS_WINDOW};
+use crate::gui::timeline_plot::{dealt_and_taken_series, show_dps_plot};
 
 /// Show the player details window as a separate viewport (independent window)
 pub fn show_player_details_window(
@@ -17,7 +18,7 @@ pub fn show_player_details_window(
 
     // Calculate window size (compact for exactly 10 bars per column)
     let window_width = 800.0;
-    let window_height = 400.0; // Reduced height for compact display
+    let window_height = 570.0; // Compact columns plus the DPS graph, attack roll and save lines
 
     ctx.show_viewport_immediate(
         egui::ViewportId::from_hash_of(format!("player_details_{}", player_name)),
@@ -171,7 +172,7 @@ pub fn show_player_details_window(
                                             // Show damage by type for this target
                                             if let Some(source_type_map) = stats.damage_by_target_source_and_type_dealt.get(target) {
                                                 ui.label("Damage by Type:");
-                                                let mut type_totals: std::collections::HashMap<String, u32> = std::collections::HashMap::new();
+                                                let mut type_totals: std::collections::HashMap<DamageType, u32> = std::collections::HashMap::new();
                                                 for type_map in source_type_map.values() {
                                                     for (dtype, dtype_amount) in type_map {
                                                         *type_totals.entry(dtype.clone()).or_default() += *dtype_amount;
@@ -201,6 +202,12 @@ pub fn show_player_details_window(
                                                     }
                                                 }
                                             }
+
+                                            // Show what this player's rolls tell about the target's AC
+                                            if let Some(bounds) = stats.ac_by_target.get(target) {
+                                                ui.add_space(5.0);
+                                                ui.label(format!("Target AC: {}", bounds.describe()));
+                                            }
                                         });
                                     }
                                 }
@@ -301,7 +308,7 @@ pub fn show_player_details_window(
                                             // Show damage types received from this attacker
                                             if let Some(source_map) = stats.damage_by_attacker_and_source_received.get(attacker) {
                                                 ui.label("Damage by Type:");
-                                                let mut type_totals: std::collections::HashMap<String, u32> = std::collections::HashMap::new();
+                                                let mut type_totals: std::collections::HashMap<DamageType, u32> = std::collections::HashMap::new();
 
                                                 // Aggregate damage types across all sources from this attacker
                                                 for source_name in source_map.keys() {
@@ -341,7 +348,100 @@ pub fn show_player_details_window(
                             });
                         });
                 });
+
+                // Rolling DPS over the fight for this player
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label(format!("Rolling DPS ({}s window)", ROLLING_DPS_WINDOW));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.label(format!("Kills: {}   Deaths: {}", stats.kills, stats.deaths));
+                    });
+                });
+                if let Some(average_bonus) = stats.average_attack_bonus() {
+                    ui.label(egui::RichText::new(attack_roll_summary(stats, average_bonus)).size(12.0));
+                }
+                if !stats.saves_against.is_empty() || !stats.saves_forced.is_empty() {
+                    // Hover for the breakdown by save type, element, spell and caster/target
+                    ui.label(egui::RichText::new(save_summary(stats)).size(12.0))
+                        .on_hover_ui(|ui| {
+                            show_save_breakdown(ui, "Own saves and spell resistance", "Caster", &stats.saves_against);
+                            ui.add_space(5.0);
+                            show_save_breakdown(ui, "Against this player's spells", "Target", &stats.saves_forced);
+                        });
+                }
+                show_dps_plot(ui, &dealt_and_taken_series(stats), 100.0);
             });
         },
     );
-}
\ No newline at end of file
+}
+
+/// One line summary of a player's logged attack rolls
+fn attack_roll_summary(stats: &CombatantStats, average_bonus: f64) -> String {
+    let mut parts = vec![
+        format!("AB: avg {:+.1}, max {:+}", average_bonus, stats.max_attack_bonus.unwrap_or(0)),
+        format!("Nat 1: {}", stats.natural_ones),
+        format!("Nat 20: {}", stats.natural_twenties),
+    ];
+    if stats.sneak_attacks > 0 {
+        parts.push(format!("Sneak: {} hits, {} dmg", stats.sneak_attacks, stats.sneak_attack_damage));
+    }
+    if stats.death_attacks > 0 {
+        parts.push(format!("Death Attack: {} hits, {} dmg", stats.death_attacks, stats.death_attack_damage));
+    }
+    if stats.off_hand_hits > 0 {
+        parts.push(format!("Off Hand: {} hits, {} dmg", stats.off_hand_hits, stats.off_hand_damage));
+    }
+    parts.join("   ")
+}
+
+/// One line summary of saves made by the player and against the player's spells
+fn save_summary(stats: &CombatantStats) -> String {
+    let own = stats.saves_against.total_saves();
+    let forced = stats.saves_forced.total_saves();
+    format!("Saves made: {}/{}   Targets saved vs. own spells: {}/{}   (hover for details)",
+        own.passed, own.attempts(), forced.passed, forced.attempts())
+}
+
+/// Save and spell resist results grouped by save type, element, spell and opponent
+fn show_save_breakdown(ui: &mut egui::Ui, title: &str, opponent_label: &str, summary: &SaveSummary) {
+    ui.label(egui::RichText::new(title).strong());
+    if summary.is_empty() {
+        ui.label("  None");
+        return;
+    }
+    for (heading, checks, passed_word) in [
+        ("By save type:", &summary.saves_by_type, "saved"),
+        ("By element:", &summary.saves_by_element, "saved"),
+        ("Spell resistance:", &summary.spell_resist_by_spell, "resisted"),
+    ] {
+        if checks.is_empty() {
+            continue;
+        }
+        ui.label(heading);
+        for (name, check) in sorted_checks(checks) {
+            ui.label(format!("  {}: {}", name, describe_checks(check, passed_word)));
+        }
+    }
+    if !summary.saves_by_opponent.is_empty() {
+        ui.label(format!("Saves by {}:", opponent_label.to_lowercase()));
+        for (name, check) in sorted_checks(&summary.saves_by_opponent) {
+            ui.label(format!("  {}: {}", name, describe_checks(check, "saved")));
+        }
+    }
+}
+
+fn sorted_checks(checks: &std::collections::HashMap<String, CheckStats>) -> Vec<(&String, &CheckStats)> {
+    let mut sorted: Vec<_> = checks.iter().collect();
+    sorted.sort_by(|a, b| b.1.attempts().cmp(&a.1.attempts()).then(a.0.cmp(b.0)));
+    sorted
+}
+
+/// e.g. "saved 3/5 (60%), roll bonus +5..+7 vs. DC 21"
+fn describe_checks(checks: &CheckStats, passed_word: &str) -> String {
+    let mut text = format!("{} {}/{} ({:.0}%)", passed_word, checks.passed, checks.attempts(), checks.pass_rate().unwrap_or(0.0));
+    if !checks.bonus_range().is_empty() {
+        text.push_str(&format!(", roll bonus {} vs. {}", checks.bonus_range(),
+            if passed_word == "resisted" { format!("SR {}", checks.dc_range()) } else { format!("DC {}", checks.dc_range()) }));
+    }
+    text
+}
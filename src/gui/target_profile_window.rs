@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use eframe::egui;
+use crate::models::{Encounter, PlayerRegistry, TargetProfile, build_target_profiles};
+
+/// Show what the logs reveal about each enemy (AC, saves, SR, immunities) across all encounters
+pub fn show_target_profile_window(
+    ctx: &egui::Context,
+    encounters: Arc<Mutex<HashMap<u64, Encounter>>>,
+    player_registry: Arc<Mutex<PlayerRegistry>>,
+    search_text: &mut String,
+    is_open: &mut bool
+) {
+    if !*is_open {
+        return;
+    }
+
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of("target_profile_window"),
+        egui::ViewportBuilder::default()
+            .with_inner_size([520.0, 480.0])
+            .with_min_inner_size([350.0, 250.0])
+            .with_resizable(true)
+            .with_decorations(false)  // Remove system decorations for custom title bar
+            .with_always_on_top()
+            .with_title("Target Profiles"),
+        |ctx, class| {
+            assert!(class == egui::ViewportClass::Immediate);
+            ctx.set_visuals(egui::Visuals::dark());
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                // Custom header bar
+                let header_rect = ui.allocate_space(egui::Vec2::new(ui.available_width(), 35.0)).1;
+
+                // Make the header draggable except for the X button area
+                let draggable_rect = egui::Rect::from_min_size(
+                    header_rect.min,
+                    egui::Vec2::new(header_rect.width() - 30.0, header_rect.height())
+                );
+                let drag_response = ui.allocate_rect(draggable_rect, egui::Sense::click_and_drag());
+                if drag_response.drag_started() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                }
+
+                ui.scope_builder(egui::UiBuilder::new().max_rect(header_rect), |ui| {
+                    ui.horizontal(|ui| {
+                        let title_pos = egui::Pos2::new(header_rect.min.x + 15.0, header_rect.center().y);
+                        ui.painter().text(title_pos, egui::Align2::LEFT_CENTER, "Target Profiles",
+                            egui::FontId::proportional(16.0), ui.visuals().text_color());
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            // Close button (X)
+                            if ui.add(egui::Button::new(egui::RichText::new("X").size(12.0))
+                                .min_size(egui::Vec2::new(25.0, 25.0))).clicked() {
+                                *is_open = false;
+                            }
+                        });
+                    });
+                });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    ui.add(egui::TextEdit::singleline(search_text).desired_width(200.0));
+                });
+                ui.add_space(4.0);
+
+                let profiles = match encounters.try_lock() {
+                    Ok(encounters) => build_target_profiles(encounters.values()),
+                    Err(_) => HashMap::new(),
+                };
+                let search = search_text.to_lowercase();
+                let mut enemies: Vec<&TargetProfile> = {
+                    let registry = player_registry.lock().ok();
+                    profiles.values()
+                        .filter(|p| !registry.as_ref().is_some_and(|r| r.is_friendly(&p.name)))
+                        .filter(|p| search.is_empty() || p.name.to_lowercase().contains(&search))
+                        .collect()
+                };
+                enemies.sort_by(|a, b| a.name.cmp(&b.name));
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        if enemies.is_empty() {
+                            ui.label("No enemies seen yet");
+                        }
+                        for profile in enemies {
+                            egui::CollapsingHeader::new(format!("{} ({} encounters)", profile.name, profile.encounters))
+                                .id_salt(("target_profile", &profile.name))
+                                .show(ui, |ui| show_profile(ui, profile));
+                        }
+                    });
+            });
+        },
+    );
+}
+
+fn show_profile(ui: &mut egui::Ui, profile: &TargetProfile) {
+    let muted = egui::Color32::from_gray(150);
+
+    ui.label(format!("AC: {}", profile.ac.describe()));
+
+    if !profile.saves.saves_by_type.is_empty() {
+        ui.label("Saving throws:");
+        let mut saves: Vec<_> = profile.saves.saves_by_type.iter().collect();
+        saves.sort_by(|a, b| a.0.cmp(b.0));
+        for (save_type, checks) in saves {
+            let mut line = format!("  {}: made {}/{}", save_type, checks.passed, checks.attempts());
+            if !checks.bonus_range().is_empty() {
+                line.push_str(&format!(", bonus {} vs. DC {}", checks.bonus_range(), checks.dc_range()));
+            }
+            ui.label(line);
+        }
+        let mut elements: Vec<_> = profile.saves.saves_by_element.iter().collect();
+        elements.sort_by(|a, b| b.1.attempts().cmp(&a.1.attempts()).then(a.0.cmp(b.0)));
+        let elements: Vec<String> = elements.iter()
+            .map(|(element, checks)| format!("{} {}/{}", element, checks.passed, checks.attempts()))
+            .collect();
+        ui.label(egui::RichText::new(format!("  vs. {}", elements.join(", "))).color(muted));
+    }
+
+    if !profile.saves.spell_resist_by_spell.is_empty() {
+        ui.label("Spell resistance:");
+        let mut spells: Vec<_> = profile.saves.spell_resist_by_spell.iter().collect();
+        spells.sort_by(|a, b| b.1.attempts().cmp(&a.1.attempts()).then(a.0.cmp(b.0)));
+        for (spell, checks) in spells {
+            let mut line = format!("  {}: resisted {}/{}", spell, checks.passed, checks.attempts());
+            if !checks.dc_range().is_empty() {
+                line.push_str(&format!(", SR {}", checks.dc_range()));
+            }
+            ui.label(line);
+        }
+    }
+
+    let immunities = profile.immunity_percentages();
+    let (resistance, reduction) = profile.resistance_and_reduction();
+    if !immunities.is_empty() || resistance > 0 || reduction > 0 {
+        ui.label("Damage absorbed:");
+        for (dtype, percent) in immunities {
+            ui.label(format!("  {} immunity: {:.0}%", dtype, percent));
+        }
+        if resistance > 0 {
+            ui.label(format!("  Damage Resistance: {} total", resistance));
+        }
+        if reduction > 0 {
+            ui.label(format!("  Damage Reduction: {} total", reduction));
+        }
+    } else {
+        ui.label(egui::RichText::new("No immunities seen").color(muted));
+    }
+}
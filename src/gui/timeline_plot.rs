@@ -0,0 +1,133 @@
+use eframe::egui;
+use crate::models::{CombatantStats, TimelineSeries, ROLLING_DPS_WINDOW};
+use crate::utils::time::format_duration;
+
+/// Line colors for the combatants in the main window graph
+const SERIES_COLORS: [egui::Color32; 6] = [
+    egui::Color32::from_rgb(80, 200, 80),
+    egui::Color32::from_rgb(90, 160, 255),
+    egui::Color32::from_rgb(255, 165, 0),
+    egui::Color32::from_rgb(220, 90, 220),
+    egui::Color32::from_rgb(240, 230, 90),
+    egui::Color32::from_rgb(90, 220, 220),
+];
+
+/// One line on a DPS plot
+pub struct PlotSeries {
+    pub label: String,
+    pub color: egui::Color32,
+    pub points: Vec<(u64, f64)>,
+}
+
+/// Rolling DPS lines for the given combatants, all over their shared time range
+pub fn combatant_series(combatants: &[(String, CombatantStats)], series: TimelineSeries, max_lines: usize) -> Vec<PlotSeries> {
+    let shown: Vec<&(String, CombatantStats)> = combatants.iter()
+        .filter(|(_, stats)| match series {
+            TimelineSeries::Dealt => !stats.timeline.dealt.is_empty(),
+            TimelineSeries::Taken => !stats.timeline.taken.is_empty(),
+        })
+        .take(max_lines)
+        .collect();
+
+    let Some((start, end)) = shared_range(shown.iter().map(|(_, stats)| stats)) else {
+        return Vec::new();
+    };
+
+    shown.iter().enumerate()
+        .map(|(i, (name, stats))| PlotSeries {
+            label: name.clone(),
+            color: SERIES_COLORS[i % SERIES_COLORS.len()],
+            points: stats.timeline.rolling_dps(series, ROLLING_DPS_WINDOW, start, end),
+        })
+        .collect()
+}
+
+/// Damage dealt and taken by a single combatant
+pub fn dealt_and_taken_series(stats: &CombatantStats) -> Vec<PlotSeries> {
+    let Some((start, end)) = stats.timeline.time_range() else {
+        return Vec::new();
+    };
+    vec![
+        PlotSeries {
+            label: "Dealt".to_string(),
+            color: egui::Color32::from_rgb(80, 200, 80),
+            points: stats.timeline.rolling_dps(TimelineSeries::Dealt, ROLLING_DPS_WINDOW, start, end),
+        },
+        PlotSeries {
+            label: "Taken".to_string(),
+            color: egui::Color32::from_rgb(220, 80, 80),
+            points: stats.timeline.rolling_dps(TimelineSeries::Taken, ROLLING_DPS_WINDOW, start, end),
+        },
+    ]
+}
+
+fn shared_range<'a, I: Iterator<Item = &'a CombatantStats>>(stats: I) -> Option<(u64, u64)> {
+    stats.filter_map(|s| s.timeline.time_range())
+        .reduce(|(a_start, a_end), (b_start, b_end)| (a_start.min(b_start), a_end.max(b_end)))
+}
+
+/// Draw a simple line plot of the series. Hovering shows the values at that second.
+pub fn show_dps_plot(ui: &mut egui::Ui, series: &[PlotSeries], height: f32) {
+    let (rect, response) = ui.allocate_exact_size(egui::Vec2::new(ui.available_width(), height), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(25));
+
+    let Some(first) = series.iter().find(|s| !s.points.is_empty()) else {
+        painter.text(rect.center(), egui::Align2::CENTER_CENTER, "No damage yet",
+            egui::FontId::proportional(12.0), egui::Color32::GRAY);
+        return;
+    };
+    let start = first.points[0].0;
+    let end = first.points[first.points.len() - 1].0;
+    let span = (end - start).max(1) as f32;
+    let max_value = series.iter()
+        .flat_map(|s| s.points.iter().map(|(_, v)| *v))
+        .fold(0.0_f64, f64::max)
+        .max(1.0);
+
+    // Leave room for the axis labels
+    let plot_rect = egui::Rect::from_min_max(rect.min + egui::vec2(4.0, 14.0), rect.max - egui::vec2(4.0, 14.0));
+    let to_screen = |t: u64, v: f64| egui::pos2(
+        plot_rect.left() + (t - start) as f32 / span * plot_rect.width(),
+        plot_rect.bottom() - (v / max_value) as f32 * plot_rect.height(),
+    );
+
+    for s in series {
+        let points: Vec<egui::Pos2> = s.points.iter().map(|(t, v)| to_screen(*t, *v)).collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, s.color)));
+    }
+
+    let small = egui::FontId::proportional(10.0);
+    painter.text(rect.left_top() + egui::vec2(4.0, 1.0), egui::Align2::LEFT_TOP,
+        format!("{:.0} dps", max_value), small.clone(), egui::Color32::GRAY);
+    painter.text(rect.left_bottom() + egui::vec2(4.0, -1.0), egui::Align2::LEFT_BOTTOM,
+        "0s", small.clone(), egui::Color32::GRAY);
+    painter.text(rect.right_bottom() + egui::vec2(-4.0, -1.0), egui::Align2::RIGHT_BOTTOM,
+        format_duration(end - start), small.clone(), egui::Color32::GRAY);
+
+    // Legend along the top
+    let mut legend_x = rect.right() - 4.0;
+    for s in series.iter().rev() {
+        let galley = painter.layout_no_wrap(s.label.clone(), small.clone(), s.color);
+        legend_x -= galley.size().x;
+        painter.galley(egui::pos2(legend_x, rect.top() + 1.0), galley, s.color);
+        legend_x -= 10.0;
+    }
+
+    // Hover: vertical marker and the values at that second
+    if let Some(pointer) = response.hover_pos() {
+        let fraction = ((pointer.x - plot_rect.left()) / plot_rect.width()).clamp(0.0, 1.0);
+        let t = start + (fraction * span).round() as u64;
+        let x = to_screen(t, 0.0).x;
+        painter.line_segment([egui::pos2(x, plot_rect.top()), egui::pos2(x, plot_rect.bottom())],
+            egui::Stroke::new(1.0, egui::Color32::from_gray(90)));
+
+        let mut lines = vec![format!("+{}", format_duration(t - start))];
+        for s in series {
+            if let Some((_, v)) = s.points.iter().find(|(pt, _)| *pt == t) {
+                lines.push(format!("{}: {:.1}", s.label, v));
+            }
+        }
+        response.on_hover_text(lines.join("\n"));
+    }
+}
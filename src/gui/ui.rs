@@ -1,12 +1,21 @@
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::time::SystemTime;
 use eframe::egui;
-use crate::models::{CombatantStats, ViewMode};
+use crate::models::{CombatantStats, DeathRecord, ViewMode, TimelineSeries, RetentionAction, PetNameRule, AlertRule, AlertTrigger};
 use crate::gui::app::NwnLogApp;
-use crate::utils::auto_save_app_settings;
-use crate::log::finder::get_default_log_directory;
+use crate::gui::timeline_plot::{combatant_series, show_dps_plot};
+use crate::utils::{auto_save_app_settings, load_buff_definitions, save_buff_timers, play_sound};
+use crate::utils::time::format_date_time;
+use crate::log::finder::{get_default_log_directory, log_directories, plan_log_retention};
+
+/// How many combatants the main window DPS graph draws
+const GRAPH_MAX_LINES: usize = 5;
+/// How many files the log retention preview lists
+const RETENTION_PREVIEW_LINES: usize = 20;
 
 impl NwnLogApp {
-    pub fn display_stats(&mut self, ui: &mut egui::Ui, stats_map: &HashMap<String, CombatantStats>) {
+    pub fn display_stats(&mut self, ui: &mut egui::Ui, stats_map: &HashMap<String, CombatantStats>, deaths: &[(u64, DeathRecord)]) {
         // Update cache only if data changed
         self.update_sorted_cache(stats_map);
 
@@ -17,6 +26,9 @@ impl NwnLogApp {
             },
             crate::models::DamageViewMode::DamageTaken => {
                 self.cached_sorted_combatants.iter().map(|(_, s)| s.total_damage_received).sum()
+            },
+            crate::models::DamageViewMode::Healing => {
+                self.cached_sorted_combatants.iter().map(|(_, s)| s.healing_done).sum()
             }
         };
 
@@ -27,9 +39,22 @@ impl NwnLogApp {
             },
             crate::models::DamageViewMode::DamageTaken => {
                 self.cached_sorted_combatants.iter().map(|(_, s)| s.total_damage_received).max().unwrap_or(1)
+            },
+            crate::models::DamageViewMode::Healing => {
+                self.cached_sorted_combatants.iter().map(|(_, s)| s.healing_done).max().unwrap_or(1)
             }
         };
         
+        // Rolling DPS of the top combatants in the current view
+        if self.show_dps_graph {
+            let series = match self.damage_view_mode {
+                crate::models::DamageViewMode::DamageTaken => TimelineSeries::Taken,
+                _ => TimelineSeries::Dealt,
+            };
+            show_dps_plot(ui, &combatant_series(&self.cached_sorted_combatants, series, GRAPH_MAX_LINES), 90.0);
+            ui.add_space(4.0);
+        }
+
         // Use scrollable area and collapsible headers that scales with window size
         let available_height = ui.available_height().max(200.0);
         egui::ScrollArea::both()
@@ -44,6 +69,9 @@ impl NwnLogApp {
                     },
                     crate::models::DamageViewMode::DamageTaken => {
                         (stats.total_damage_received, total_encounter_damage)
+                    },
+                    crate::models::DamageViewMode::Healing => {
+                        (stats.healing_done, total_encounter_damage)
                     }
                 };
 
@@ -63,6 +91,9 @@ impl NwnLogApp {
                     },
                     crate::models::DamageViewMode::DamageTaken => {
                         stats.total_damage_received
+                    },
+                    crate::models::DamageViewMode::Healing => {
+                        stats.healing_done
                     }
                 };
 
@@ -86,9 +117,9 @@ impl NwnLogApp {
                         egui::Vec2::new(bar_width, header_height)
                     );
 
-                    // Check if this is a known player
+                    // Check if this is a known player (or their pet)
                     let is_player = if let Ok(registry) = self.player_registry.lock() {
-                        registry.is_player(name)
+                        registry.is_friendly(name)
                     } else {
                         false
                     };
@@ -115,7 +146,13 @@ impl NwnLogApp {
 
                 // Draw damage info on the right in white
                 if display_damage > 0 {
-                    let damage_info = if let Some(dps) = stats.calculate_dps() {
+                    // Healing view shows HPS instead of DPS
+                    let rate = if self.damage_view_mode == crate::models::DamageViewMode::Healing {
+                        stats.calculate_hps()
+                    } else {
+                        stats.calculate_dps()
+                    };
+                    let damage_info = if let Some(dps) = rate {
                         format!("{} ({:.1}, {}%)", display_damage, dps, damage_percentage)
                     } else {
                         format!("{} ({}%)", display_damage, damage_percentage)
@@ -125,17 +162,62 @@ impl NwnLogApp {
                         egui::FontId::proportional(14.0), egui::Color32::WHITE);
                 }
                 
+                // Show the effective/overheal split when hovering a healer
+                let header_response = if self.damage_view_mode == crate::models::DamageViewMode::Healing && stats.healing_done > 0 {
+                    header_response.on_hover_text(format!(
+                        "Effective: {}\nOverheal (est.): {}\nHealing received: {}",
+                        stats.effective_healing_done, stats.overhealing_done, stats.healing_received
+                    ))
+                } else {
+                    header_response
+                };
+
                 // Handle click to open player details window
                 if header_response.clicked() {
                     let is_open = self.open_detail_windows.entry(name.clone()).or_insert(false);
                     *is_open = true;
                 }
             }
+
+            // Death log with the damage taken just before each death
+            if !deaths.is_empty() {
+                ui.add_space(4.0);
+                egui::CollapsingHeader::new(format!("Deaths ({})", deaths.len()))
+                    .id_salt("death_log")
+                    .show(ui, |ui| {
+                        for (i, (start_time, death)) in deaths.iter().enumerate() {
+                            egui::CollapsingHeader::new(format!("+{}s {}", death.timestamp.saturating_sub(*start_time), death.summary()))
+                                .id_salt(("death", i))
+                                .show(ui, |ui| {
+                                    for event in &death.recap {
+                                        ui.label(egui::RichText::new(format!("+{}s {}", event.timestamp.saturating_sub(*start_time), event.describe()))
+                                            .size(12.0)
+                                            .color(egui::Color32::from_rgb(220, 150, 150)));
+                                    }
+                                });
+                        }
+                    });
+            }
         });
     }
 }
 
 impl eframe::App for NwnLogApp {
+    /// Save the fight in progress to the encounter history (and the buff timers, if kept) before closing
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_current_encounter_to_history();
+
+        let persist_buff_timers = self.settings_ref.as_ref()
+            .and_then(|settings| settings.lock().ok().map(|settings| settings.persist_buff_timers))
+            .unwrap_or(false);
+        if persist_buff_timers
+            && let Ok(tracker) = self.buff_tracker.lock()
+            && let Err(e) = save_buff_timers(&tracker)
+        {
+            eprintln!("Failed to save buff timers: {}", e);
+        }
+    }
+
     /// This function is called on every frame to update the GUI.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Request continuous repaints to keep the app updating even when not focused
@@ -275,10 +357,30 @@ impl eframe::App for NwnLogApp {
                     self.buff_window_spawned = !self.buff_window_spawned;
                 }
 
+                // Party and enemy effects button
+                if ui.add_sized([55.0, 20.0], egui::Button::new("Effects").selected(self.effects_open)).clicked() {
+                    self.effects_open = !self.effects_open;
+                }
+
                 // Logs button
                 if ui.add_sized([55.0, 20.0], egui::Button::new("Logs").selected(self.logs_window_open)).clicked() {
                     self.logs_window_open = !self.logs_window_open;
                 }
+
+                // DPS graph toggle
+                if ui.add_sized([55.0, 20.0], egui::Button::new("Graph").selected(self.show_dps_graph)).clicked() {
+                    self.show_dps_graph = !self.show_dps_graph;
+                }
+
+                // Target profiles button
+                if ui.add_sized([60.0, 20.0], egui::Button::new("Targets").selected(self.target_profiles_open)).clicked() {
+                    self.target_profiles_open = !self.target_profiles_open;
+                }
+
+                // Encounter history button
+                if ui.add_sized([60.0, 20.0], egui::Button::new("History").selected(self.history_open)).clicked() {
+                    self.history_open = !self.history_open;
+                }
             });
 
             // Second row: Damage view mode and filter buttons
@@ -294,6 +396,10 @@ impl eframe::App for NwnLogApp {
                     .selected(self.damage_view_mode == crate::models::DamageViewMode::DamageTaken)).clicked() {
                     self.damage_view_mode = crate::models::DamageViewMode::DamageTaken;
                 }
+                if ui.add_sized([60.0, 20.0], egui::Button::new("Healing")
+                    .selected(self.damage_view_mode == crate::models::DamageViewMode::Healing)).clicked() {
+                    self.damage_view_mode = crate::models::DamageViewMode::Healing;
+                }
 
                 ui.add_space(20.0);
                 ui.label("Filter:");
@@ -331,7 +437,13 @@ impl eframe::App for NwnLogApp {
                             
                             for encounter in sorted_encounters {
                                 let mut is_selected = self.selected_encounter_ids.contains(&encounter.id);
-                                let display_name = encounter.get_display_name();
+                                // Reopened history encounters show their date, since they're not from this log
+                                let start = if self.history_encounter_ids.contains(&encounter.id) {
+                                    format_date_time(encounter.start_time)
+                                } else {
+                                    encounter.start_clock_time()
+                                };
+                                let display_name = format!("{} {}", start, encounter.get_display_name());
                                 
                                 // Make the entire row clickable by using a horizontal layout
                                 ui.horizontal(|ui| {
@@ -344,7 +456,8 @@ impl eframe::App for NwnLogApp {
                                     }
                                     
                                     // Make the text also clickable
-                                    let text_response = ui.selectable_label(is_selected, display_name);
+                                    let text_response = ui.selectable_label(is_selected, display_name)
+                                        .on_hover_text(encounter.time_range());
                                     if text_response.clicked() {
                                         if is_selected {
                                             self.selected_encounter_ids.remove(&encounter.id);
@@ -357,6 +470,26 @@ impl eframe::App for NwnLogApp {
                         }
                     });
                 
+                // Export the selected encounters (all of them when nothing is selected)
+                ui.horizontal(|ui| {
+                    ui.label("Export:");
+                    if ui.button("CSV").clicked() {
+                        self.export_encounters(crate::export::ExportFormat::Csv);
+                    }
+                    if ui.button("JSON").clicked() {
+                        self.export_encounters(crate::export::ExportFormat::Json);
+                    }
+                    if ui.button("HTML").clicked() {
+                        self.export_encounters(crate::export::ExportFormat::Html);
+                    }
+                    if ui.button("Compare").on_hover_text("Compare two encounters or combatants side by side").clicked() {
+                        self.open_comparison();
+                    }
+                    if let Some(status) = &self.export_status {
+                        ui.label(egui::RichText::new(status).small().weak());
+                    }
+                });
+
                 // Show selection summary
                 if !self.selected_encounter_ids.is_empty() {
                     ui.horizontal(|ui| {
@@ -373,6 +506,20 @@ impl eframe::App for NwnLogApp {
                             }
                         }
                     });
+
+                    // Fix up the automatic segmentation; both reparse the log
+                    ui.horizontal(|ui| {
+                        if self.selected_encounter_ids.len() >= 2 {
+                            if ui.button("Merge").on_hover_text("Combine the selected encounters into one").clicked() {
+                                self.merge_selected_encounters();
+                            }
+                        } else if self.selected_encounter_ids.len() == 1 {
+                            ui.add(egui::DragValue::new(&mut self.split_offset).range(1..=3600).speed(1.0).prefix("at ").suffix(" s"));
+                            if ui.button("Split").on_hover_text("Start a new encounter this many seconds into the selected one").clicked() {
+                                self.split_selected_encounter();
+                            }
+                        }
+                    });
                 }
             }
             
@@ -380,10 +527,11 @@ impl eframe::App for NwnLogApp {
             
             // Get the stats data using the new system
             let stats_to_display = Some(self.get_current_stats());
+            let deaths = self.get_current_deaths();
             
             // Now display the UI with the collected data
             if let Some(stats_map) = stats_to_display {
-                self.display_stats(ui, &stats_map);
+                self.display_stats(ui, &stats_map, &deaths);
             }
         }); // End CentralPanel
 
@@ -464,6 +612,16 @@ impl eframe::App for NwnLogApp {
             }
         }
 
+        // Show party and enemy effects window if requested
+        if self.effects_open
+            && let Some(settings_ref) = &self.settings_ref
+        {
+            crate::gui::show_effects_window(ctx, self.buff_tracker.clone(),
+                self.player_registry.clone(),
+                settings_ref.clone(),
+                &mut self.effects_open);
+        }
+
         // Show logs window if requested
         if self.logs_window_open {
             if let Some(settings_ref) = &self.settings_ref {
@@ -473,6 +631,29 @@ impl eframe::App for NwnLogApp {
             }
         }
 
+        // Show target profiles window if requested
+        if self.target_profiles_open {
+            crate::gui::show_target_profile_window(ctx, self.encounters.clone(),
+                self.player_registry.clone(),
+                &mut self.target_profile_search,
+                &mut self.target_profiles_open);
+        }
+
+        // Show comparison window if requested
+        if self.compare_open {
+            crate::gui::show_compare_window(ctx, self.encounters.clone(),
+                &mut self.compare_state,
+                &mut self.compare_open);
+        }
+
+        // Show encounter history window if requested
+        if self.history_open {
+            let to_open = crate::gui::show_history_window(ctx, self.history.clone(),
+                &mut self.history_search,
+                &mut self.history_open);
+            self.open_history_entries(to_open);
+        }
+
         // Show player detail windows
         let current_stats = self.get_current_stats();
         let mut windows_to_close = Vec::new();
@@ -499,9 +680,14 @@ impl eframe::App for NwnLogApp {
         for player_name in windows_to_close {
             self.open_detail_windows.remove(&player_name);
         }
+
+        self.show_alerts(ctx);
     }
 }
 
+/// How long an alert flashes the overlay
+const ALERT_FLASH_SECONDS: f64 = 2.0;
+
 impl NwnLogApp {
     /// Show the options configuration window
     fn show_options_window(&mut self, ctx: &egui::Context) {
@@ -515,6 +701,41 @@ impl NwnLogApp {
                 ui.heading("Character Settings");
                 ui.separator();
 
+                // Per-character profiles, switched automatically when the main player changes character
+                if let Some(settings_ref) = &self.settings_ref
+                    && let Ok(mut settings) = settings_ref.lock()
+                {
+                    let mut selected = settings.active_character.clone();
+                    let mut removed = None;
+                    ui.horizontal(|ui| {
+                        ui.label("Profile:");
+                        egui::ComboBox::from_id_salt("character_profile")
+                            .selected_text(selected.clone().unwrap_or_else(|| "No character yet".to_string()))
+                            .width(150.0)
+                            .show_ui(ui, |ui| {
+                                for character in settings.character_profiles.keys() {
+                                    ui.selectable_value(&mut selected, Some(character.clone()), character);
+                                }
+                            });
+                        if let Some(character) = &selected
+                            && ui.small_button("X").on_hover_text("Forget this character's profile").clicked()
+                        {
+                            removed = Some(character.clone());
+                        }
+                    });
+                    let mut changed = false;
+                    if let Some(character) = removed {
+                        settings.remove_character_profile(&character);
+                        changed = true;
+                    } else if let Some(character) = selected {
+                        changed = settings.switch_character(&character);
+                    }
+                    if changed {
+                        auto_save_app_settings(&settings);
+                    }
+                    ui.small("Values below are saved per character and swapped in when you switch characters");
+                }
+
                 // Caster Level setting
                 ui.horizontal(|ui| {
                     ui.label("Caster Level:");
@@ -547,26 +768,37 @@ impl NwnLogApp {
                 ui.heading("Feats");
                 ui.separator();
 
-                // Extended Divine Might toggle
-                if let Some(settings_ref) = &self.settings_ref {
-                    if let Ok(mut settings) = settings_ref.lock() {
-                        let mut extended_divine_might = settings.extended_divine_might;
-                        if ui.checkbox(&mut extended_divine_might, "Extended Divine Might").changed() {
-                            settings.extended_divine_might = extended_divine_might;
-                            auto_save_app_settings(&*settings);
+                // One toggle per feat/item modifier named in buffs.json
+                let modifier_names = self.buff_tracker.lock()
+                    .map(|tracker| tracker.modifier_names())
+                    .unwrap_or_default();
+                if let Some(settings_ref) = &self.settings_ref
+                    && let Ok(mut settings) = settings_ref.lock()
+                {
+                    for name in &modifier_names {
+                        let mut enabled = settings.buff_modifiers.contains(name);
+                        if ui.checkbox(&mut enabled, name).changed() {
+                            settings.set_buff_modifier(name, enabled);
+                            auto_save_app_settings(&settings);
                         }
                     }
                 }
-
-                // Extended Divine Shield toggle
-                if let Some(settings_ref) = &self.settings_ref {
-                    if let Ok(mut settings) = settings_ref.lock() {
-                        let mut extended_divine_shield = settings.extended_divine_shield;
-                        if ui.checkbox(&mut extended_divine_shield, "Extended Divine Shield").changed() {
-                            settings.extended_divine_shield = extended_divine_shield;
-                            auto_save_app_settings(&*settings);
-                        }
+                ui.horizontal(|ui| {
+                    if ui.button("Reload buffs.json").on_hover_text("Pick up edits to the buff definitions without restarting").clicked()
+                        && let Ok(mut tracker) = self.buff_tracker.lock()
+                    {
+                        tracker.definitions = load_buff_definitions();
                     }
+                    ui.small(format!("{} buffs tracked", self.buff_tracker.lock().map(|tracker| tracker.definitions.len()).unwrap_or(0)));
+                });
+
+                if let Some(settings_ref) = &self.settings_ref
+                    && let Ok(mut settings) = settings_ref.lock()
+                    && ui.checkbox(&mut settings.persist_buff_timers, "Keep buff timers between runs")
+                        .on_hover_text("Running timers are rebuilt from the current log on start; this also keeps them when the log they were cast in is no longer the current one")
+                        .changed()
+                {
+                    auto_save_app_settings(&settings);
                 }
 
                 ui.add_space(10.0);
@@ -584,6 +816,154 @@ impl NwnLogApp {
                     }
                 }
 
+                ui.add_space(10.0);
+                ui.heading("Alerts");
+                ui.separator();
+
+                let mut test_rule = None;
+                if let Some(settings_ref) = &self.settings_ref
+                    && let Ok(mut settings) = settings_ref.lock()
+                {
+                    let before = settings.alert_rules.clone();
+                    let mut removed = None;
+                    for (index, rule) in settings.alert_rules.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut rule.enabled, "");
+                            egui::ComboBox::from_id_salt(("alert_trigger", index))
+                                .selected_text(rule.trigger.kind_name())
+                                .width(110.0)
+                                .show_ui(ui, |ui| {
+                                    for template in AlertTrigger::templates() {
+                                        let selected = template.kind_name() == rule.trigger.kind_name();
+                                        if ui.selectable_label(selected, template.kind_name()).clicked() && !selected {
+                                            rule.trigger = template;
+                                        }
+                                    }
+                                });
+                            match &mut rule.trigger {
+                                AlertTrigger::BuffExpiring { buff, seconds } => {
+                                    ui.add(egui::TextEdit::singleline(buff).desired_width(110.0).hint_text("any buff"));
+                                    ui.add(egui::DragValue::new(seconds).range(1..=600).speed(1.0).suffix(" s"));
+                                }
+                                AlertTrigger::BuffWoreOff { buff } => {
+                                    ui.add(egui::TextEdit::singleline(buff).desired_width(110.0).hint_text("any buff"));
+                                }
+                                AlertTrigger::BigHit { damage } => {
+                                    ui.label("more than");
+                                    ui.add(egui::DragValue::new(damage).range(1..=10000).speed(1.0));
+                                }
+                                AlertTrigger::EnemyCasts { spell } => {
+                                    ui.add(egui::TextEdit::singleline(spell).desired_width(110.0).hint_text("any spell"));
+                                }
+                            }
+                            if ui.small_button("X").clicked() {
+                                removed = Some(index);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add_space(24.0);
+                            ui.checkbox(&mut rule.flash, "Flash");
+                            ui.add(egui::TextEdit::singleline(&mut rule.sound).desired_width(170.0).hint_text("sound file (.wav)"));
+                            if ui.small_button("Test").clicked() {
+                                test_rule = Some(rule.clone());
+                            }
+                        });
+                    }
+                    if let Some(index) = removed {
+                        settings.alert_rules.remove(index);
+                    }
+                    if ui.button("Add alert").clicked() {
+                        settings.alert_rules.push(AlertRule::default());
+                    }
+                    if settings.alert_rules != before {
+                        auto_save_app_settings(&settings);
+                    }
+                }
+                if let Some(rule) = test_rule
+                    && let Ok(mut alerts) = self.alerts.lock()
+                {
+                    alerts.test(&rule);
+                }
+
+                ui.add_space(10.0);
+                ui.heading("Encounters");
+                ui.separator();
+
+                // Encounter segmentation settings (apply to lines parsed from now on)
+                if let Some(settings_ref) = &self.settings_ref
+                    && let Ok(mut settings) = settings_ref.lock()
+                {
+                    ui.horizontal(|ui| {
+                        ui.label("Timeout:");
+                        let mut timeout = settings.encounter_timeout;
+                        if ui.add(egui::DragValue::new(&mut timeout).range(2..=60).speed(1.0).suffix(" s")).changed() {
+                            settings.set_encounter_timeout(timeout);
+                            auto_save_app_settings(&settings);
+                        }
+                    });
+
+                    let mut changed = false;
+                    changed |= ui.checkbox(&mut settings.end_on_primary_target_death, "End when the main target dies").changed();
+                    changed |= ui.checkbox(&mut settings.start_on_initiative, "Start on Initiative Roll").changed();
+                    changed |= ui.checkbox(&mut settings.rest_ends_encounter, "Resting ends the encounter").changed();
+                    if changed {
+                        auto_save_app_settings(&settings);
+                    }
+                }
+
+                ui.add_space(10.0);
+                ui.heading("Pets");
+                ui.separator();
+
+                let mut reparse = false;
+                if let Some(settings_ref) = &self.settings_ref
+                    && let Ok(mut settings) = settings_ref.lock()
+                {
+                    let before = (settings.merge_pet_damage, settings.pet_name_rules.clone());
+                    ui.checkbox(&mut settings.merge_pet_damage, "Count pet damage as the owner's")
+                        .on_hover_text("Summons, companions and henchmen are shown as a source under their owner");
+                    ui.small("Pet name rules (regex -> owner, $name inserts a capture group):");
+                    let mut removed = None;
+                    for (index, rule) in settings.pet_name_rules.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut rule.pattern).desired_width(150.0).hint_text("^(?P<owner>.+)'s Hawk$"));
+                            ui.label("->");
+                            ui.add(egui::TextEdit::singleline(&mut rule.owner).desired_width(90.0).hint_text("$owner"));
+                            if ui.small_button("X").clicked() {
+                                removed = Some(index);
+                            }
+                        });
+                    }
+                    if let Some(index) = removed {
+                        settings.pet_name_rules.remove(index);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Add rule").clicked() {
+                            settings.pet_name_rules.push(PetNameRule::default());
+                        }
+                        reparse = ui.button("Apply to log").on_hover_text("Parse the log again with these pet settings").clicked();
+                    });
+
+                    if settings.merge_pet_damage != before.0 {
+                        reparse = true;
+                    }
+                    if (settings.merge_pet_damage, &settings.pet_name_rules) != (before.0, &before.1) {
+                        auto_save_app_settings(&settings);
+                    }
+                }
+                if let Ok(registry) = self.player_registry.lock()
+                    && !registry.pet_owners.is_empty()
+                {
+                    let mut pets: Vec<String> = registry.pet_owners.iter()
+                        .map(|(pet, owner)| format!("{} ({})", pet, owner))
+                        .collect();
+                    pets.sort();
+                    ui.small(format!("Summons seen: {}", pets.join(", ")));
+                }
+                if reparse {
+                    self.request_reparse();
+                }
+
                 ui.add_space(10.0);
                 ui.heading("Log Directory");
                 ui.separator();
@@ -676,6 +1056,81 @@ impl NwnLogApp {
                     ui.small("No log directory auto-detected");
                 }
 
+                ui.add_space(10.0);
+                ui.heading("Import Old Logs");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Logs:");
+                    ui.add(egui::TextEdit::singleline(&mut self.import_path_text).desired_width(220.0))
+                        .on_hover_text("A folder of logs (searched recursively) or log files, separated by ';'");
+                    if ui.button("Import").clicked() {
+                        self.import_logs();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Replay").on_hover_text("Play one log file as if NWN were writing it").clicked() {
+                        self.replay_log();
+                    }
+                    ui.add(egui::DragValue::new(&mut self.replay_speed).range(0.25..=100.0).speed(0.25).suffix("x"));
+                });
+                if self.is_showing_import() {
+                    ui.horizontal(|ui| {
+                        ui.small("Showing old logs instead of the live log");
+                        if ui.small_button("Back to live log").clicked() {
+                            self.return_to_live_log();
+                        }
+                    });
+                }
+                if let Some(status) = &self.import_status {
+                    ui.small(status);
+                }
+
+                ui.add_space(10.0);
+                ui.heading("Log Retention");
+                ui.separator();
+
+                // Old logs are only archived or deleted if the user opts in
+                if let Some(settings_ref) = &self.settings_ref
+                    && let Ok(mut settings) = settings_ref.lock()
+                {
+                    let before = settings.log_retention.clone();
+                    let retention = &mut settings.log_retention;
+                    ui.checkbox(&mut retention.enabled, "Clean up old log files");
+                    ui.add_enabled_ui(retention.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut retention.action, RetentionAction::Archive, "Archive")
+                                .on_hover_text("Move into archive/<date>/ in the log folder");
+                            ui.radio_value(&mut retention.action, RetentionAction::Delete, "Delete");
+                        });
+                        retention_limit_row(ui, "Older than", &mut retention.max_age_days, 1..=3650, 30, " days");
+                        retention_limit_row(ui, "More than", &mut retention.max_files, 1..=1000, 10, " logs");
+                        retention_limit_row(ui, "Over", &mut retention.max_total_mb, 1..=100_000, 500, " MB");
+                    });
+                    if settings.log_retention != before {
+                        auto_save_app_settings(&settings);
+                    }
+
+                    if ui.button("Preview").on_hover_text("List the logs these settings would touch, without changing anything").clicked() {
+                        let dirs = log_directories(settings.log_directory.as_deref());
+                        let candidates = plan_log_retention(&settings.log_retention, &dirs, SystemTime::now());
+                        self.retention_preview = Some(candidates.iter().map(|c| c.summary()).collect());
+                    }
+                }
+                if let Some(preview) = &self.retention_preview {
+                    if preview.is_empty() {
+                        ui.small("No log files would be touched");
+                    } else {
+                        ui.small(format!("{} log file(s) would be touched:", preview.len()));
+                        for line in preview.iter().take(RETENTION_PREVIEW_LINES) {
+                            ui.small(line);
+                        }
+                        if preview.len() > RETENTION_PREVIEW_LINES {
+                            ui.small(format!("...and {} more", preview.len() - RETENTION_PREVIEW_LINES));
+                        }
+                    }
+                }
+
                 // Display current settings info
                 ui.add_space(10.0);
                 ui.separator();
@@ -687,6 +1142,59 @@ impl NwnLogApp {
         self.show_options = show_options;
     }
 
+    /// Play queued alerts (checking buff timers first) and flash the overlay for those that ask for it
+    fn show_alerts(&mut self, ctx: &egui::Context) {
+        let rules = self.settings_ref.as_ref()
+            .and_then(|settings| settings.lock().ok().map(|settings| settings.alert_rules.clone()))
+            .unwrap_or_default();
+        let now = ctx.input(|i| i.time);
+
+        if let Ok(mut alerts) = self.alerts.lock() {
+            if let Ok(tracker) = self.buff_tracker.lock() {
+                alerts.check_buffs(&rules, &tracker.get_active_buffs());
+            }
+            for alert in alerts.drain() {
+                eprintln!("Alert: {}", alert.message);
+                if let Some(sound) = &alert.sound {
+                    play_sound(sound);
+                }
+                if alert.flash {
+                    self.alert_flash = Some((alert.message, now + ALERT_FLASH_SECONDS));
+                }
+            }
+        }
+
+        let Some((message, until)) = &self.alert_flash else {
+            return;
+        };
+        if now >= *until {
+            self.alert_flash = None;
+            return;
+        }
+
+        // Pulse a red wash over the whole overlay with the alert's message on top
+        let pulse = ((now * 8.0).sin() * 0.5 + 0.5) as f32;
+        let screen = ctx.screen_rect();
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("alert_flash")));
+        painter.rect_filled(screen, 0.0, egui::Color32::from_rgba_unmultiplied(220, 40, 40, (40.0 + 80.0 * pulse) as u8));
+        painter.rect_stroke(screen.shrink(2.0), 0.0, egui::Stroke::new(4.0, egui::Color32::from_rgb(255, 80, 80)), egui::StrokeKind::Inside);
+        painter.text(screen.center(), egui::Align2::CENTER_CENTER, message,
+            egui::FontId::proportional(22.0), egui::Color32::WHITE);
+    }
+
     // Buff window is now handled as an independent application - no embedded window needed
 
-}
\ No newline at end of file
+}
+
+/// An optional retention limit: a checkbox to turn it on and the limit itself
+fn retention_limit_row<T: egui::emath::Numeric>(ui: &mut egui::Ui, label: &str, limit: &mut Option<T>, range: RangeInclusive<T>, default: T, suffix: &str) {
+    ui.horizontal(|ui| {
+        let mut enabled = limit.is_some();
+        if ui.checkbox(&mut enabled, label).changed() {
+            *limit = enabled.then_some(default);
+        }
+        if let Some(value) = limit {
+            ui.add(egui::DragValue::new(value).range(range).speed(1.0).suffix(suffix));
+        }
+    });
+}
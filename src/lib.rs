@@ -3,4 +3,8 @@ pub mod models;
 pub mod parsing;
 pub mod utils;
 pub mod log;
-pub mod gui;
\ No newline at end of file
+pub mod session;
+pub mod export;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod cli;
@@ -0,0 +1,114 @@
+use crate::models::DamageType;
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub content: String,
+    pub log_type: LogType,
+}
+
+/// Structure to accumulate damage immunity absorptions for the same target at the same timestamp
+#[derive(Debug, Clone)]
+pub struct DamageImmunityAccumulator {
+    pub timestamp: String,
+    pub target: String,
+    pub absorptions: Vec<(u32, DamageType)>, // (amount, damage_type)
+    pub resistance_total: u32, // Total from Damage Resistance/Reduction (no type specified)
+    pub is_attack_immunity: bool, // True if these immunities are from attacks (not spells)
+}
+
+impl DamageImmunityAccumulator {
+    pub fn to_log_entry(&self) -> LogEntry {
+        let mut parts = Vec::new();
+        for (amount, dtype) in &self.absorptions {
+            // Normalize damage type names - remove "Energy" suffix for display
+            let display_type = dtype.name().replace(" Energy", "");
+            parts.push(format!("{} {}", amount, display_type));
+        }
+        let content = format!("{} : Damage Immunity absorbs {}", self.target, parts.join(", "));
+
+        LogEntry {
+            timestamp: self.timestamp.clone(),
+            content,
+            log_type: LogType::CombatDamage,
+        }
+    }
+
+    /// Format absorptions as a string for appending to damage lines: "absorbs: N (X Type1 Y Type2), resisted: M"
+    pub fn format_absorption_suffix(&self) -> String {
+        use std::collections::HashMap;
+
+        let mut result = String::new();
+
+        // Format typed absorptions (Damage Immunity)
+        if !self.absorptions.is_empty() {
+            // Aggregate by damage type
+            let mut type_totals: HashMap<&DamageType, u32> = HashMap::new();
+
+            for (amount, dtype) in &self.absorptions {
+                *type_totals.entry(dtype).or_insert(0) += amount;
+            }
+
+            // Calculate total and format parts
+            let total: u32 = type_totals.values().sum();
+            let mut parts: Vec<String> = type_totals.iter()
+                .map(|(dtype, amount)| format!("{} {}", amount, dtype))
+                .collect();
+            parts.sort(); // Sort for consistent ordering
+
+            result.push_str(&format!(", absorbs: {} ({})", total, parts.join(" ")));
+        }
+
+        // Format resistance total (Damage Resistance/Reduction)
+        if self.resistance_total > 0 {
+            result.push_str(&format!(", resisted: {}", self.resistance_total));
+        }
+
+        result
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogType {
+    Chat,
+    CombatRoll,
+    CombatDamage,
+    CombatOther,
+    SpellCast,
+    BuffExpiration,
+    Other,
+}
+
+impl LogType {
+    pub fn from_content(content: &str) -> Self {
+        if content.contains("has joined as a player") || content.contains("has left as a player") || content.contains("has joined the party") || content.contains("wore off") || content.contains("has worn off") {
+            LogType::Other  // Join/Leave and buff expiration messages
+        } else if content.contains("SPELL RESIST:") {
+            LogType::SpellCast  // Spell resist checks
+        } else if content.contains("Initiative Roll :") || content.contains("SAVE:") || content.contains("Healed") && content.contains("hit points") || content.contains("Immune to Critical Hits") || content.contains("You triggered a Trap!") {
+            LogType::CombatOther  // Initiative rolls, saves, healing, immunity messages, traps
+        } else if content.contains("attacks") || content.contains("*hit*") || content.contains("*miss*") || content.contains("*critical hit*") {
+            LogType::CombatRoll  // Attack rolls (hit/miss/critical)
+        } else if content.contains("damages") || content.contains("Damage Immunity") {
+            LogType::CombatDamage  // Damage messages
+        } else if content.contains("casts") || content.contains("casting") {
+            LogType::SpellCast
+        } else if content.to_lowercase().contains("[talk]") || content.to_lowercase().contains("[tell]") || content.to_lowercase().contains("[party]") || content.to_lowercase().contains("[shout]") || content.to_lowercase().contains("[say]") || content.to_lowercase().contains("[whisper]") || content.to_lowercase().contains("[server]") || content.to_lowercase().contains("[dm]") {
+            LogType::Chat
+        } else {
+            LogType::Other
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            LogType::Chat => "Chat",
+            LogType::CombatRoll => "Combat Rolls",
+            LogType::CombatDamage => "Combat Damage",
+            LogType::CombatOther => "Combat - Other",
+            LogType::SpellCast => "Spell Casting",
+            LogType::BuffExpiration => "Buff Expiration",
+            LogType::Other => "Other",
+        }
+    }
+}
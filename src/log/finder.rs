@@ -1,7 +1,9 @@
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::SystemTime;
+use chrono::{DateTime, Local};
+use crate::models::{LogRetention, RetentionAction};
 
 pub fn find_latest_log_file_in_dir(dir: &Path) -> Option<PathBuf> {
     fs::read_dir(dir).ok()?.filter_map(|entry| entry.ok())
@@ -152,84 +154,353 @@ pub fn get_unix_documents_logs_path() -> PathBuf {
     path
 }
 
-pub fn cleanup_old_log_files() -> io::Result<usize> {
-    let mut cleaned_count = 0;
-    let one_day_ago = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs() - 86400) // 86400 seconds = 1 day
-        .unwrap_or(0);
-    
+/// Directories whose logs the retention policy manages: the custom log directory if one
+/// is set, otherwise every default location for this platform
+pub fn log_directories(custom_dir: Option<&str>) -> Vec<PathBuf> {
+    if let Some(custom_path) = custom_dir {
+        return vec![PathBuf::from(custom_path)];
+    }
     if cfg!(windows) {
-        // Clean both OneDrive and regular paths on Windows
-        let paths = vec![get_onedrive_logs_path(), get_regular_logs_path()];
-        
-        for log_dir in paths {
-            if log_dir.exists() {
-                let entries = fs::read_dir(&log_dir)?;
-                for entry in entries.filter_map(|e| e.ok()) {
-                    let path = entry.path();
-                    if path.is_file() {
-                        if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
-                            if filename.starts_with("nwclientLog") && filename.ends_with(".txt") {
-                                if let Ok(metadata) = path.metadata() {
-                                    if let Ok(modified) = metadata.modified() {
-                                        if let Ok(duration) = modified.duration_since(UNIX_EPOCH) {
-                                            if duration.as_secs() < one_day_ago {
-                                                match fs::remove_file(&path) {
-                                                    Ok(_) => {
-                                                        println!("Deleted old log file: {:?}", path);
-                                                        cleaned_count += 1;
-                                                    }
-                                                    Err(e) => {
-                                                        println!("Failed to delete {:?}: {}", path, e);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+        vec![get_onedrive_logs_path(), get_regular_logs_path()]
+    } else {
+        vec![get_unix_logs_path(), get_unix_documents_logs_path()]
+    }
+}
+
+/// A log file that the retention policy would archive or delete
+#[derive(Debug, Clone)]
+pub struct RetentionCandidate {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    /// Which limit the file breaks, e.g. "older than 30 days"
+    pub reason: String,
+}
+
+impl RetentionCandidate {
+    /// One line for the dry-run listing, e.g. "nwclientLog2.txt (1.5 MB, older than 30 days)"
+    pub fn summary(&self) -> String {
+        format!("{} ({:.1} MB, {})",
+            self.path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default(),
+            self.size as f64 / (1024.0 * 1024.0), self.reason)
+    }
+}
+
+/// Dry run: the log files in `dirs` that break the policy's limits, oldest first.
+/// The newest log in each directory (the one NWN is writing) is always kept.
+pub fn plan_log_retention(policy: &LogRetention, dirs: &[PathBuf], now: SystemTime) -> Vec<RetentionCandidate> {
+    let mut candidates = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else { continue };
+        let mut logs: Vec<(PathBuf, u64, SystemTime)> = entries.filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && is_log_file_name(path))
+            .filter_map(|path| {
+                let metadata = path.metadata().ok()?;
+                Some((path, metadata.len(), metadata.modified().ok()?))
+            })
+            .collect();
+        // Newest first
+        logs.sort_by_key(|(_, _, modified)| std::cmp::Reverse(*modified));
+
+        let mut total_size = 0u64;
+        for (index, (path, size, modified)) in logs.into_iter().enumerate() {
+            total_size += size;
+            if index == 0 {
+                continue;
             }
+
+            let age_days = now.duration_since(modified).map(|age| age.as_secs() / 86400).unwrap_or(0);
+            let reason = if let Some(max_age) = policy.max_age_days.filter(|&days| age_days > days as u64) {
+                format!("older than {} days", max_age)
+            } else if let Some(max_files) = policy.max_files.filter(|&count| index >= count as usize) {
+                format!("more than {} logs", max_files)
+            } else if let Some(max_mb) = policy.max_total_mb.filter(|&mb| total_size > mb * 1024 * 1024) {
+                format!("over {} MB of logs", max_mb)
+            } else {
+                continue;
+            };
+            candidates.push(RetentionCandidate { path, size, modified, reason });
         }
-    } else {
-        // Unix cleanup logic - clean both locations
-        let paths = vec![get_unix_logs_path(), get_unix_documents_logs_path()];
-
-        for log_dir in paths {
-            if log_dir.exists() {
-                let entries = fs::read_dir(&log_dir)?;
-                for entry in entries.filter_map(|e| e.ok()) {
-                    let path = entry.path();
-                    if path.is_file() {
-                        if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
-                            if filename.starts_with("nwclientLog") && filename.ends_with(".txt") {
-                                if let Ok(metadata) = path.metadata() {
-                                    if let Ok(modified) = metadata.modified() {
-                                        if let Ok(duration) = modified.duration_since(UNIX_EPOCH) {
-                                            if duration.as_secs() < one_day_ago {
-                                                match fs::remove_file(&path) {
-                                                    Ok(_) => {
-                                                        println!("Deleted old log file: {:?}", path);
-                                                        cleaned_count += 1;
-                                                    }
-                                                    Err(e) => {
-                                                        println!("Failed to delete {:?}: {}", path, e);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+    }
+    candidates.sort_by_key(|candidate| candidate.modified);
+    candidates
+}
+
+/// Archive or delete the given logs. Archived logs go to `archive/<YYYY-MM-DD>/` next to them,
+/// with the time added to the name since NWN reuses nwclientLog1-4.txt.
+pub fn apply_log_retention(policy: &LogRetention, candidates: &[RetentionCandidate]) -> io::Result<usize> {
+    let mut handled = 0;
+    for candidate in candidates {
+        let result = match policy.action {
+            RetentionAction::Delete => fs::remove_file(&candidate.path),
+            RetentionAction::Archive => archive_log_file(candidate),
+        };
+        let verb = match policy.action {
+            RetentionAction::Archive => "archive",
+            RetentionAction::Delete => "delete",
+        };
+        match result {
+            Ok(_) => {
+                eprintln!("Log retention: {}d {:?} ({})", verb, candidate.path, candidate.reason);
+                handled += 1;
             }
+            Err(e) => eprintln!("Failed to {} {:?}: {}", verb, candidate.path, e),
+        }
+    }
+    Ok(handled)
+}
+
+/// Apply the retention policy to every managed log directory. Does nothing unless enabled.
+pub fn run_log_retention(policy: &LogRetention, custom_dir: Option<&str>) -> io::Result<usize> {
+    if !policy.enabled {
+        return Ok(0);
+    }
+    let candidates = plan_log_retention(policy, &log_directories(custom_dir), SystemTime::now());
+    apply_log_retention(policy, &candidates)
+}
+
+fn archive_log_file(candidate: &RetentionCandidate) -> io::Result<()> {
+    let dir = candidate.path.parent().unwrap_or(Path::new("."));
+    let modified = DateTime::<Local>::from(candidate.modified);
+    let archive_dir = dir.join("archive").join(modified.format("%Y-%m-%d").to_string());
+    fs::create_dir_all(&archive_dir)?;
+
+    let stem = candidate.path.file_stem().and_then(|s| s.to_str()).unwrap_or("nwclientLog");
+    let name = format!("{}_{}", stem, modified.format("%H%M%S"));
+    // Never overwrite an earlier archive that happens to share the name
+    let archived = (1..)
+        .map(|n| archive_dir.join(if n == 1 { format!("{}.txt", name) } else { format!("{}_{}.txt", name, n) }))
+        .find(|path| !path.exists())
+        .unwrap();
+    // Moving across drives isn't possible with rename, so copy and delete instead
+    if fs::rename(&candidate.path, &archived).is_err() {
+        fs::copy(&candidate.path, &archived)?;
+        fs::remove_file(&candidate.path)?;
+    }
+    Ok(())
+}
+
+/// Expand files and folders of old logs into a list of log files, oldest first.
+/// Folders are searched recursively (so `archive/<date>/` copies are found) for `nwclientLog*.txt`;
+/// files given directly are used whatever their name. Logs are ordered by when NWN last wrote to
+/// them, since the rotated names (nwclientLog1-4.txt) say nothing about their age.
+pub fn collect_log_files(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_log_files_in_dir(path, &mut files)?;
+        } else if path.is_file() {
+            files.push(path.clone());
+        } else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} does not exist", path.display())));
+        }
+    }
+
+    let mut files: Vec<(SystemTime, PathBuf)> = files.into_iter()
+        .map(|path| (path.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH), path))
+        .collect();
+    files.sort();
+    files.dedup_by(|a, b| a.1 == b.1);
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+fn collect_log_files_in_dir(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_log_files_in_dir(&path, files)?;
+        } else if is_log_file_name(&path) {
+            files.push(path);
         }
     }
-    
-    Ok(cleaned_count)
-}
\ No newline at end of file
+    Ok(())
+}
+
+fn is_log_file_name(path: &Path) -> bool {
+    path.file_name().and_then(|s| s.to_str())
+        .is_some_and(|s| s.starts_with("nwclientLog") && s.ends_with(".txt"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const MB: usize = 1024 * 1024;
+
+    /// A fresh directory under the system temp dir, removed again when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("nwn_parser_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn now() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_767_182_400)
+    }
+
+    /// Write a file of `size` bytes last modified `age_days` before `now()`
+    fn write_log(dir: &TempDir, name: &str, size: usize, age_days: u64) -> PathBuf {
+        let path = dir.0.join(name);
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        set_age(&path, age_days);
+        path
+    }
+
+    fn set_age(path: &Path, age_days: u64) {
+        let modified = now() - Duration::from_secs(age_days * 86400);
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    fn policy(max_age_days: Option<u32>, max_files: Option<u32>, max_total_mb: Option<u64>) -> LogRetention {
+        LogRetention { enabled: true, action: RetentionAction::Archive, max_age_days, max_files, max_total_mb }
+    }
+
+    /// File names and reasons of the planned candidates
+    fn plan(policy: &LogRetention, dirs: &[&TempDir]) -> Vec<(String, String)> {
+        let dirs: Vec<PathBuf> = dirs.iter().map(|dir| dir.0.clone()).collect();
+        plan_log_retention(policy, &dirs, now()).into_iter()
+            .map(|candidate| (candidate.path.file_name().unwrap().to_string_lossy().into_owned(), candidate.reason))
+            .collect()
+    }
+
+    fn planned(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries.iter().map(|(name, reason)| (name.to_string(), reason.to_string())).collect()
+    }
+
+    #[test]
+    fn age_limit_takes_logs_older_than_it() {
+        let dir = TempDir::new("retention_age");
+        write_log(&dir, "nwclientLog1.txt", 10, 0);
+        write_log(&dir, "nwclientLog2.txt", 10, 30);
+        write_log(&dir, "nwclientLog3.txt", 10, 31);
+        write_log(&dir, "nwclientLog4.txt", 10, 90);
+        write_log(&dir, "notes.txt", 10, 90);
+
+        assert_eq!(plan(&policy(Some(30), None, None), &[&dir]), planned(&[
+            ("nwclientLog4.txt", "older than 30 days"),
+            ("nwclientLog3.txt", "older than 30 days"),
+        ]));
+    }
+
+    #[test]
+    fn count_limit_keeps_the_newest_logs() {
+        let dir = TempDir::new("retention_count");
+        for (n, age_days) in [(1, 3), (2, 1), (3, 4), (4, 2)] {
+            write_log(&dir, &format!("nwclientLog{}.txt", n), 10, age_days);
+        }
+
+        assert_eq!(plan(&policy(None, Some(2), None), &[&dir]), planned(&[
+            ("nwclientLog3.txt", "more than 2 logs"),
+            ("nwclientLog1.txt", "more than 2 logs"),
+        ]));
+    }
+
+    #[test]
+    fn size_limit_keeps_the_newest_logs_that_fit() {
+        let dir = TempDir::new("retention_size");
+        write_log(&dir, "nwclientLog1.txt", MB, 1);
+        write_log(&dir, "nwclientLog2.txt", MB, 2);
+        write_log(&dir, "nwclientLog3.txt", MB, 3);
+
+        assert_eq!(plan(&policy(None, None, Some(2)), &[&dir]), planned(&[
+            ("nwclientLog3.txt", "over 2 MB of logs"),
+        ]));
+    }
+
+    #[test]
+    fn age_beats_count_beats_size() {
+        let dir = TempDir::new("retention_precedence");
+        write_log(&dir, "nwclientLog1.txt", MB, 0);
+        write_log(&dir, "nwclientLog2.txt", MB, 1);
+        write_log(&dir, "nwclientLog3.txt", MB, 2);
+        write_log(&dir, "nwclientLog4.txt", MB, 60);
+
+        // Log 2 only breaks the size limit, log 3 also the count, log 4 all three
+        assert_eq!(plan(&policy(Some(30), Some(2), Some(1)), &[&dir]), planned(&[
+            ("nwclientLog4.txt", "older than 30 days"),
+            ("nwclientLog3.txt", "more than 2 logs"),
+            ("nwclientLog2.txt", "over 1 MB of logs"),
+        ]));
+    }
+
+    #[test]
+    fn newest_log_in_each_directory_is_always_kept() {
+        let single = TempDir::new("retention_newest_single");
+        write_log(&single, "nwclientLog1.txt", MB, 400);
+        let pair = TempDir::new("retention_newest_pair");
+        write_log(&pair, "nwclientLog1.txt", MB, 200);
+        write_log(&pair, "nwclientLog2.txt", MB, 300);
+
+        assert_eq!(plan(&policy(Some(1), Some(1), Some(0)), &[&single, &pair]), planned(&[
+            ("nwclientLog2.txt", "older than 1 days"),
+        ]));
+    }
+
+    #[test]
+    fn dry_run_leaves_files_alone_and_describes_them() {
+        let dir = TempDir::new("retention_dry_run");
+        let newest = write_log(&dir, "nwclientLog1.txt", 10, 0);
+        let old = write_log(&dir, "nwclientLog2.txt", MB * 3 / 2, 45);
+
+        let candidates = plan_log_retention(&policy(Some(30), None, None), std::slice::from_ref(&dir.0), now());
+        let summaries: Vec<String> = candidates.iter().map(|candidate| candidate.summary()).collect();
+        assert_eq!(summaries, vec!["nwclientLog2.txt (1.5 MB, older than 30 days)".to_string()]);
+        assert!(newest.exists() && old.exists());
+        assert!(!dir.0.join("archive").exists());
+    }
+
+    #[test]
+    fn delete_removes_only_the_candidates() {
+        let dir = TempDir::new("retention_delete");
+        let newest = write_log(&dir, "nwclientLog1.txt", 10, 0);
+        let old = write_log(&dir, "nwclientLog2.txt", 10, 45);
+        let policy = LogRetention { action: RetentionAction::Delete, ..policy(Some(30), None, None) };
+
+        let candidates = plan_log_retention(&policy, std::slice::from_ref(&dir.0), now());
+        assert_eq!(apply_log_retention(&policy, &candidates).unwrap(), 1);
+        assert!(newest.exists());
+        assert!(!old.exists());
+    }
+
+    #[test]
+    fn archiving_never_overwrites_an_earlier_archive() {
+        let dir = TempDir::new("retention_archive");
+        write_log(&dir, "nwclientLog1.txt", 10, 0);
+        let policy = policy(Some(30), None, None);
+
+        // NWN reuses the name, so the same log name can be archived twice with the same time
+        for contents in ["first", "second"] {
+            let old = dir.0.join("nwclientLog2.txt");
+            fs::write(&old, contents).unwrap();
+            set_age(&old, 45);
+
+            let candidates = plan_log_retention(&policy, std::slice::from_ref(&dir.0), now());
+            assert_eq!(apply_log_retention(&policy, &candidates).unwrap(), 1);
+            assert!(!old.exists());
+        }
+
+        let archived = collect_log_files(&[dir.0.join("archive")]).unwrap();
+        let mut names: Vec<String> = archived.iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 2);
+        assert!(names[0].starts_with("nwclientLog2_") && names[1].ends_with("_2.txt"), "{:?}", names);
+        let mut contents: Vec<String> = archived.iter().map(|path| fs::read_to_string(path).unwrap()).collect();
+        contents.sort();
+        assert_eq!(contents, vec!["first".to_string(), "second".to_string()]);
+    }
+}
@@ -1,4 +1,5 @@
+pub mod entry;
 pub mod finder;
 pub mod watcher;
 
-pub use watcher::log_watcher_thread;
\ No newline at end of file
+pub use watcher::{log_watcher_thread, LogSource};
\ No newline at end of file
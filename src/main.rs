@@ -1,48 +1,64 @@
-
-use eframe::{egui, NativeOptions};
-use egui::ViewportBuilder;
 use std::error::Error;
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::thread;
-
-// Module declarations
-mod models;
-mod parsing;
-mod log;
-mod gui;
-mod utils;
 
-// Re-exports for convenience
-use gui::NwnLogApp;
-use log::log_watcher_thread;
+use nwn_parser::cli;
+use nwn_parser::log::LogSource;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    // Set up the shared state for encounters
-    let encounters = Arc::new(Mutex::new(HashMap::new()));
-    let current_encounter_id = Arc::new(Mutex::new(None));
-    let encounter_counter = Arc::new(Mutex::new(1));
+    // Headless subcommands (e.g. `nwn_parser report <file>`) run without the GUI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let log_source = match cli::parse_args(&args) {
+        Ok(command) => {
+            let log_source = command.log_source();
+            if cli::run(command)? {
+                return Ok(());
+            }
+            log_source
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    run_gui(log_source)
+}
+
+#[cfg(feature = "gui")]
+fn run_gui(log_source: LogSource) -> Result<(), Box<dyn Error>> {
+    use std::thread;
+    use eframe::{egui, NativeOptions};
+    use egui::ViewportBuilder;
+    use nwn_parser::gui::NwnLogApp;
+    use nwn_parser::log::log_watcher_thread;
+    use nwn_parser::session::CombatSession;
 
     // Create the application state to get shared references
     let mut app = NwnLogApp::new();
-    let player_registry = app.player_registry.clone();
-    let buff_tracker = app.buff_tracker.clone();
-    let settings = app.settings_ref.clone().unwrap();
-    let log_reload_requested = app.log_reload_requested.clone();
-    let logs_state = app.logs_window_state.recent_logs.clone();
 
-    let encounters_clone = encounters.clone();
-    let current_encounter_clone = current_encounter_id.clone();
-    let counter_clone = encounter_counter.clone();
-    let registry_clone = player_registry.clone();
-    let buff_tracker_clone = buff_tracker.clone();
-    let settings_clone = settings.clone();
-    let reload_requested_clone = log_reload_requested.clone();
-    let logs_state_clone = logs_state.clone();
+    // The combat session owns the engine state; the GUI keeps clones of its shared parts
+    let mut session = CombatSession::new();
+    session.player_registry = app.player_registry.clone();
+    session.buff_tracker = app.buff_tracker.clone();
+    session.settings = app.settings_ref.clone().unwrap();
+    session.persist_player_registry = true;
+    session.history = app.history.clone();
+    session.persist_history = true;
+    session.persist_settings = true;
+    session.alerts = app.alerts.clone();
+
+    app.encounters = session.encounters.clone();
+    app.current_encounter_id = session.current_encounter_id.clone();
+    app.encounter_counter = session.encounter_counter.clone();
+    app.encounter_edits = session.encounter_edits.clone();
+
+    let reload_requested_clone = app.log_reload_requested.clone();
+    *app.log_source.lock().unwrap() = log_source;
+    let log_source_clone = app.log_source.clone();
+    let logs_state_clone = app.logs_window_state.recent_logs.clone();
 
     // Spawn the background thread for log watching.
     thread::spawn(move || {
-        log_watcher_thread(encounters_clone, current_encounter_clone, counter_clone, registry_clone, buff_tracker_clone, settings_clone, reload_requested_clone, logs_state_clone);
+        log_watcher_thread(session, reload_requested_clone, log_source_clone, logs_state_clone);
     });
 
     // Configure the native window options for a borderless, custom GUI.
@@ -56,12 +72,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             .with_always_on_top(), // Keep window always on top
         ..Default::default()
     };
-    
-    // Update the app with the shared state
-    app.encounters = encounters;
-    app.current_encounter_id = current_encounter_id;
-    app.encounter_counter = encounter_counter;
-    
+
     eframe::run_native(
         "NWN Log Overlay",
         native_options,
@@ -73,4 +84,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     )?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui(_log_source: LogSource) -> Result<(), Box<dyn Error>> {
+    eprintln!("This build has no GUI (the `gui` feature is disabled).\n\n{}", cli::USAGE);
+    std::process::exit(2);
+}
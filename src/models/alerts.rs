@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::models::buffs::ActiveBuff;
+use crate::utils::get_current_timestamp;
+
+/// What sets an alert off. Empty buff/spell names match any buff/spell.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AlertTrigger {
+    /// One of the main character's tracked buffs has `seconds` or less left
+    BuffExpiring { buff: String, seconds: u32 },
+    /// A "wore off" line for one of the main character's buffs
+    BuffWoreOff { buff: String },
+    /// The main character took more than `damage` in a single hit
+    BigHit { damage: u32 },
+    /// A combatant that isn't a player or a pet, and has fought them this encounter, cast the spell
+    EnemyCasts { spell: String },
+}
+
+impl AlertTrigger {
+    /// One of each kind, for the options window's picker
+    pub fn templates() -> [AlertTrigger; 4] {
+        [
+            AlertTrigger::BuffExpiring { buff: String::new(), seconds: 10 },
+            AlertTrigger::BuffWoreOff { buff: String::new() },
+            AlertTrigger::BigHit { damage: 50 },
+            AlertTrigger::EnemyCasts { spell: String::new() },
+        ]
+    }
+
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            AlertTrigger::BuffExpiring { .. } => "Buff expiring",
+            AlertTrigger::BuffWoreOff { .. } => "Buff wore off",
+            AlertTrigger::BigHit { .. } => "Big hit taken",
+            AlertTrigger::EnemyCasts { .. } => "Enemy casts",
+        }
+    }
+}
+
+impl fmt::Display for AlertTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_any = |name: &str, any: &'static str| if name.is_empty() { any.to_string() } else { name.to_string() };
+        match self {
+            AlertTrigger::BuffExpiring { buff, seconds } => write!(f, "{} has {}s left", or_any(buff, "A buff"), seconds),
+            AlertTrigger::BuffWoreOff { buff } => write!(f, "{} wore off", or_any(buff, "A buff")),
+            AlertTrigger::BigHit { damage } => write!(f, "Hit for more than {}", damage),
+            AlertTrigger::EnemyCasts { spell } => write!(f, "Enemy casts {}", or_any(spell, "a spell")),
+        }
+    }
+}
+
+/// A user alert from the settings: a trigger and what to do when it fires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub enabled: bool,
+    pub trigger: AlertTrigger,
+    /// Sound file to play (WAV plays everywhere); empty for none
+    #[serde(default)]
+    pub sound: String,
+    /// Flash the overlay window
+    #[serde(default)]
+    pub flash: bool,
+}
+
+impl Default for AlertRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trigger: AlertTrigger::BuffExpiring { buff: String::new(), seconds: 10 },
+            sound: String::new(),
+            flash: true,
+        }
+    }
+}
+
+/// Something that happened in the live log that alert rules can match
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertEvent {
+    BuffWoreOff { buff: String },
+    HitTaken { damage: u32, attacker: String },
+    EnemyCast { caster: String, spell: String },
+}
+
+/// An alert that fired and hasn't been played yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub message: String,
+    pub sound: Option<String>,
+    pub flash: bool,
+    pub time: u64,
+}
+
+impl Alert {
+    fn new(rule: &AlertRule, message: String) -> Self {
+        Self {
+            message,
+            sound: Some(rule.sound.trim().to_string()).filter(|sound| !sound.is_empty()),
+            flash: rule.flash,
+            time: get_current_timestamp(),
+        }
+    }
+}
+
+fn name_matches(wanted: &str, name: &str) -> bool {
+    let wanted = wanted.trim();
+    wanted.is_empty() || wanted.eq_ignore_ascii_case(name)
+}
+
+/// Alerts waiting to be played, filled by the log watcher (log events) and the GUI (buff timers)
+#[derive(Debug, Default)]
+pub struct AlertQueue {
+    pending: Vec<Alert>,
+    /// Buff timers (rule trigger, buff name, start time) already warned about, so each rule warns once per cast.
+    /// Keyed on the trigger rather than the rule's position, so editing the rule list doesn't re-warn.
+    warned_buffs: HashSet<(AlertTrigger, String, u64)>,
+}
+
+impl AlertQueue {
+    /// Queue an alert for every enabled rule the event matches
+    pub fn check_event(&mut self, rules: &[AlertRule], event: &AlertEvent) {
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            let message = match (&rule.trigger, event) {
+                (AlertTrigger::BuffWoreOff { buff }, AlertEvent::BuffWoreOff { buff: worn_off })
+                    if name_matches(buff, worn_off) => format!("{} wore off", worn_off),
+                (AlertTrigger::BigHit { damage }, AlertEvent::HitTaken { damage: taken, attacker })
+                    if taken > damage => format!("{} hit you for {}", attacker, taken),
+                (AlertTrigger::EnemyCasts { spell }, AlertEvent::EnemyCast { caster, spell: cast })
+                    if name_matches(spell, cast) => format!("{} casts {}", caster, cast),
+                _ => continue,
+            };
+            self.pending.push(Alert::new(rule, message));
+        }
+    }
+
+    /// Queue a warning for buffs that have dropped to a rule's threshold, once per cast
+    pub fn check_buffs(&mut self, rules: &[AlertRule], buffs: &[&ActiveBuff]) {
+        self.warned_buffs.retain(|(_, name, start_time)| {
+            buffs.iter().any(|buff| &buff.name == name && buff.start_time == *start_time)
+        });
+
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            let AlertTrigger::BuffExpiring { buff: wanted, seconds } = &rule.trigger else {
+                continue;
+            };
+            for buff in buffs {
+                let remaining = buff.remaining_seconds();
+                if remaining > 0
+                    && remaining <= *seconds as i64
+                    && name_matches(wanted, &buff.name)
+                    && self.warned_buffs.insert((rule.trigger.clone(), buff.name.clone(), buff.start_time))
+                {
+                    self.pending.push(Alert::new(rule, format!("{} has {}s left", buff.name, remaining)));
+                }
+            }
+        }
+    }
+
+    /// Queue a rule's sound and flash right away, to try it out
+    pub fn test(&mut self, rule: &AlertRule) {
+        self.pending.push(Alert::new(rule, rule.trigger.to_string()));
+    }
+
+    /// Take the alerts to play
+    pub fn drain(&mut self) -> Vec<Alert> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(trigger: AlertTrigger) -> AlertRule {
+        AlertRule { trigger, ..AlertRule::default() }
+    }
+
+    fn messages(queue: &mut AlertQueue) -> Vec<String> {
+        queue.drain().into_iter().map(|alert| alert.message).collect()
+    }
+
+    /// A buff cast `elapsed` seconds ago that lasts `duration` seconds
+    fn buff(name: &str, elapsed: u64, duration: u64) -> ActiveBuff {
+        ActiveBuff::new(name.to_string(), "Thorin".to_string(), get_current_timestamp() - elapsed, duration)
+    }
+
+    #[test]
+    fn events_fire_the_rules_they_match() {
+        let rules = vec![
+            rule(AlertTrigger::BuffWoreOff { buff: "divine might".to_string() }),
+            rule(AlertTrigger::BuffWoreOff { buff: String::new() }),
+            rule(AlertTrigger::BigHit { damage: 50 }),
+            rule(AlertTrigger::EnemyCasts { spell: "Implosion".to_string() }),
+            AlertRule { enabled: false, ..rule(AlertTrigger::EnemyCasts { spell: String::new() }) },
+        ];
+        let mut queue = AlertQueue::default();
+
+        queue.check_event(&rules, &AlertEvent::BuffWoreOff { buff: "Divine Might".to_string() });
+        assert_eq!(messages(&mut queue), vec!["Divine Might wore off", "Divine Might wore off"]);
+
+        queue.check_event(&rules, &AlertEvent::BuffWoreOff { buff: "Divine Shield".to_string() });
+        assert_eq!(messages(&mut queue), vec!["Divine Shield wore off"]);
+
+        queue.check_event(&rules, &AlertEvent::HitTaken { damage: 50, attacker: "Orc".to_string() });
+        queue.check_event(&rules, &AlertEvent::HitTaken { damage: 51, attacker: "Orc".to_string() });
+        assert_eq!(messages(&mut queue), vec!["Orc hit you for 51"]);
+
+        queue.check_event(&rules, &AlertEvent::EnemyCast { caster: "Lich".to_string(), spell: "Implosion".to_string() });
+        queue.check_event(&rules, &AlertEvent::EnemyCast { caster: "Lich".to_string(), spell: "Fireball".to_string() });
+        assert_eq!(messages(&mut queue), vec!["Lich casts Implosion"]);
+    }
+
+    #[test]
+    fn alerts_carry_the_rules_sound_and_flash() {
+        let rules = vec![
+            AlertRule { sound: "  alarm.wav ".to_string(), flash: false, ..rule(AlertTrigger::BigHit { damage: 0 }) },
+            AlertRule { sound: "   ".to_string(), flash: true, ..rule(AlertTrigger::BigHit { damage: 0 }) },
+        ];
+        let mut queue = AlertQueue::default();
+        queue.check_event(&rules, &AlertEvent::HitTaken { damage: 1, attacker: "Orc".to_string() });
+
+        let alerts = queue.drain();
+        assert_eq!(alerts.len(), 2);
+        assert_eq!((alerts[0].sound.as_deref(), alerts[0].flash), (Some("alarm.wav"), false));
+        assert_eq!((alerts[1].sound.as_deref(), alerts[1].flash), (None, true));
+        assert!(queue.drain().is_empty());
+    }
+
+    #[test]
+    fn expiring_buffs_warn_once_per_cast() {
+        let rules = vec![rule(AlertTrigger::BuffExpiring { buff: String::new(), seconds: 10 })];
+        let mut queue = AlertQueue::default();
+        let fresh = buff("Divine Shield", 0, 100);
+        let expired = buff("Divine Favor", 200, 120);
+        let expiring = buff("Divine Might", 95, 100);
+
+        queue.check_buffs(&rules, &[&fresh, &expired]);
+        assert!(messages(&mut queue).is_empty());
+
+        queue.check_buffs(&rules, &[&fresh, &expired, &expiring]);
+        let warnings = messages(&mut queue);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Divine Might has "), "{}", warnings[0]);
+
+        queue.check_buffs(&rules, &[&fresh, &expiring]);
+        assert!(messages(&mut queue).is_empty());
+
+        // Recasting starts a new timer, which warns again when it runs low
+        let recast = buff("Divine Might", 92, 100);
+        queue.check_buffs(&rules, &[&fresh, &recast]);
+        assert_eq!(messages(&mut queue).len(), 1);
+    }
+
+    #[test]
+    fn editing_the_rule_list_does_not_repeat_warnings() {
+        let might_rule = rule(AlertTrigger::BuffExpiring { buff: "Divine Might".to_string(), seconds: 10 });
+        let any_rule = rule(AlertTrigger::BuffExpiring { buff: String::new(), seconds: 10 });
+        let mut queue = AlertQueue::default();
+        let might = buff("Divine Might", 95, 100);
+
+        queue.check_buffs(&[might_rule.clone(), any_rule.clone()], &[&might]);
+        assert_eq!(messages(&mut queue).len(), 2);
+
+        // Deleting the first rule moves the second one up
+        queue.check_buffs(std::slice::from_ref(&any_rule), &[&might]);
+        assert!(messages(&mut queue).is_empty());
+
+        // Reordering doesn't either
+        queue.check_buffs(&[any_rule, might_rule], &[&might]);
+        assert!(messages(&mut queue).is_empty());
+
+        // A new rule warns about the running timer
+        queue.check_buffs(&[rule(AlertTrigger::BuffExpiring { buff: String::new(), seconds: 20 })], &[&might]);
+        assert_eq!(messages(&mut queue).len(), 1);
+    }
+
+    #[test]
+    fn expiring_rules_only_watch_their_buff() {
+        let rules = vec![
+            rule(AlertTrigger::BuffExpiring { buff: "Divine Might".to_string(), seconds: 10 }),
+            rule(AlertTrigger::BuffExpiring { buff: String::new(), seconds: 30 }),
+        ];
+        let mut queue = AlertQueue::default();
+        let might = buff("Divine Might", 95, 100);
+        let shield = buff("Divine Shield", 80, 100);
+
+        queue.check_buffs(&rules, &[&might, &shield]);
+        let mut warnings = messages(&mut queue);
+        warnings.sort();
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[0].starts_with("Divine Might has ") && warnings[1].starts_with("Divine Might has "));
+        assert!(warnings[2].starts_with("Divine Shield has "));
+    }
+
+    #[test]
+    fn testing_a_rule_queues_it_right_away() {
+        let mut queue = AlertQueue::default();
+        queue.test(&rule(AlertTrigger::BigHit { damage: 80 }));
+        assert_eq!(messages(&mut queue), vec!["Hit for more than 80"]);
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use crate::models::AppSettings;
+use crate::models::effects::{ActiveEffect, EffectKind, EffectTracker};
 use crate::utils::get_current_timestamp;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,11 +13,11 @@ pub struct ActiveBuff {
 }
 
 impl ActiveBuff {
-    pub fn new(name: String, caster: String, duration_seconds: u64) -> Self {
+    pub fn new(name: String, caster: String, start_time: u64, duration_seconds: u64) -> Self {
         Self {
             name,
             caster,
-            start_time: get_current_timestamp(),
+            start_time,
             duration_seconds,
         }
     }
@@ -32,9 +33,156 @@ impl ActiveBuff {
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Seconds in a game round
+const ROUND_SECONDS: f64 = 6.0;
+
+/// A feat or item that changes a buff's duration while it is enabled in the options
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuffModifier {
+    /// Name shown next to its checkbox; modifiers with the same name are toggled together
+    pub name: String,
+    /// Applied to the duration first
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// Then these rounds are added
+    #[serde(default)]
+    pub extra_rounds: f64,
+}
+
+/// How long one buff lasts, as listed in buffs.json. The duration in seconds is
+/// `max(base_seconds + 6 * floor(rounds_per_caster_level * CL + rounds_per_charisma * CHA), min_seconds) * multiplier`,
+/// then each enabled modifier is applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuffDefinition {
+    /// Spell name as it appears in "casts" lines
+    pub name: String,
+    #[serde(default)]
+    pub base_seconds: f64,
+    #[serde(default)]
+    pub rounds_per_caster_level: f64,
+    #[serde(default)]
+    pub rounds_per_charisma: f64,
+    /// Floor for formulas that a negative charisma modifier can push below zero
+    #[serde(default)]
+    pub min_seconds: f64,
+    /// Extend Spell (2), Persistent Spell or anything else that always applies
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    #[serde(default)]
+    pub modifiers: Vec<BuffModifier>,
+    /// A debuff: tracked on the target when it fails its save or spell resistance check,
+    /// and never shown in the buff window
+    #[serde(default)]
+    pub debuff: bool,
+}
+
+fn default_multiplier() -> f64 {
+    1.0
+}
+
+impl BuffDefinition {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            base_seconds: 0.0,
+            rounds_per_caster_level: 0.0,
+            rounds_per_charisma: 0.0,
+            min_seconds: 0.0,
+            multiplier: 1.0,
+            modifiers: Vec::new(),
+            debuff: false,
+        }
+    }
+
+    /// Duration for the caster level and charisma modifier in the settings
+    pub fn duration_seconds(&self, settings: &AppSettings) -> u64 {
+        let caster_level = settings.caster_level.max(1) as f64;
+        let charisma = settings.charisma_modifier.max(-10) as f64;
+        let rounds = (self.rounds_per_caster_level * caster_level + self.rounds_per_charisma * charisma).floor();
+        let mut seconds = (self.base_seconds + rounds * ROUND_SECONDS).max(self.min_seconds) * self.multiplier;
+        for modifier in &self.modifiers {
+            if settings.buff_modifiers.contains(&modifier.name) {
+                seconds = seconds * modifier.multiplier + modifier.extra_rounds * ROUND_SECONDS;
+            }
+        }
+        seconds.max(0.0).round() as u64
+    }
+}
+
+/// The buffs tracked out of the box, written to buffs.json the first time the app runs
+pub fn default_buff_definitions() -> Vec<BuffDefinition> {
+    let flat = |name: &str, seconds: f64, multiplier: f64| BuffDefinition {
+        base_seconds: seconds,
+        multiplier,
+        ..BuffDefinition::new(name)
+    };
+    let per_level = |name: &str, multiplier: f64| BuffDefinition {
+        rounds_per_caster_level: 1.0,
+        multiplier,
+        ..BuffDefinition::new(name)
+    };
+    // 2 rounds per point of charisma, 10 more rounds with the Extended item
+    let per_charisma = |name: &str, item: &str| BuffDefinition {
+        rounds_per_charisma: 2.0,
+        min_seconds: 10.0,
+        modifiers: vec![BuffModifier { name: item.to_string(), multiplier: 1.0, extra_rounds: 10.0 }],
+        ..BuffDefinition::new(name)
+    };
+
+    let debuff = |name: &str, rounds_per_caster_level: f64| BuffDefinition {
+        rounds_per_caster_level,
+        debuff: true,
+        ..BuffDefinition::new(name)
+    };
+
+    vec![
+        flat("Divine Favor", 120.0, 1.0),
+        per_charisma("Divine Might", "Extended Divine Might"),
+        per_charisma("Divine Shield", "Extended Divine Shield"),
+        // Assumed extended
+        per_level("Divine Power", 2.0),
+        per_level("Tenser's Transformation", 2.0),
+        flat("Greater Sanctuary", 20.0, 2.0),
+        // 2 seconds + 1 round per 2 caster levels, assumed extended
+        BuffDefinition {
+            base_seconds: 2.0,
+            rounds_per_caster_level: 0.5,
+            multiplier: 2.0,
+            ..BuffDefinition::new("Bigby's Interposing Hand")
+        },
+        per_level("Acid Fog", 1.0),
+        per_level("Cloudkill", 1.0),
+        per_level("Mestil's Acid Sheath", 2.0),
+        per_level("Elemental Shield", 2.0),
+        per_level("Death Armor", 2.0),
+        per_level("Blade Thirst", 2.0),
+        debuff("Slow", 1.0),
+        debuff("Hold Person", 1.0),
+        debuff("Hold Monster", 1.0),
+        debuff("Blindness/Deafness", 1.0),
+        debuff("Doom", 10.0),
+    ]
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BuffTracker {
     pub active_buffs: HashMap<String, ActiveBuff>, // buff_name -> ActiveBuff
+    /// Which spells are tracked and for how long (from buffs.json)
+    #[serde(skip, default = "default_buff_definitions")]
+    pub definitions: Vec<BuffDefinition>,
+    /// Buffs and debuffs on every combatant, for the effects panel
+    #[serde(default)]
+    pub effects: EffectTracker,
+    /// Seconds the log being read runs behind the wall clock: about zero for the live log,
+    /// the log's age for a replay. Timers start at the cast's log time plus this.
+    #[serde(skip)]
+    log_time_offset: u64,
+}
+
+impl Default for BuffTracker {
+    fn default() -> Self {
+        Self::with_definitions(default_buff_definitions())
+    }
 }
 
 impl BuffTracker {
@@ -42,15 +190,74 @@ impl BuffTracker {
         Self::default()
     }
 
-    pub fn add_buff(&mut self, name: String, caster: String, settings: &AppSettings) {
-        if let Some(duration) = Self::calculate_buff_duration(&name, settings) {
-            let buff = ActiveBuff::new(name.clone(), caster, duration);
+    pub fn with_definitions(definitions: Vec<BuffDefinition>) -> Self {
+        Self {
+            active_buffs: HashMap::new(),
+            definitions,
+            effects: EffectTracker::default(),
+            log_time_offset: 0,
+        }
+    }
+
+    /// Note the log time of a line as it is read live, so timers started by lines that
+    /// are being played back count down from when they were read
+    pub fn sync_log_time(&mut self, log_time: u64) {
+        self.log_time_offset = get_current_timestamp().saturating_sub(log_time);
+    }
+
+    /// Read a log that was already written: timers start at their log times
+    pub fn reset_log_time(&mut self) {
+        self.log_time_offset = 0;
+    }
+
+    /// Wall-clock start time for a timer begun by a line logged at `log_time`
+    pub fn timer_start(&self, log_time: u64) -> u64 {
+        log_time + self.log_time_offset
+    }
+
+    /// Names of every feat/item modifier in the definitions, in order of first use
+    pub fn modifier_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for modifier in self.definitions.iter().flat_map(|definition| &definition.modifiers) {
+            if !names.contains(&modifier.name) {
+                names.push(modifier.name.clone());
+            }
+        }
+        names
+    }
+
+    pub fn add_buff(&mut self, name: String, caster: String, log_time: u64, settings: &AppSettings) {
+        if let Some(duration) = self.calculate_buff_duration(&name, settings) {
+            let buff = ActiveBuff::new(name.clone(), caster, self.timer_start(log_time), duration);
             self.active_buffs.insert(name, buff);
         }
     }
 
-    pub fn is_trackable_buff(spell_name: &str, settings: &AppSettings) -> bool {
-        Self::calculate_buff_duration(spell_name, settings).is_some()
+    pub fn is_trackable_buff(&self, spell_name: &str) -> bool {
+        self.definition(spell_name, false).is_some()
+    }
+
+    fn definition(&self, spell_name: &str, debuff: bool) -> Option<&BuffDefinition> {
+        self.definitions.iter()
+            .find(|definition| definition.debuff == debuff && definition.name.eq_ignore_ascii_case(spell_name))
+    }
+
+    /// A party member cast a buff; it is tracked on the caster
+    pub fn track_cast(&mut self, caster: &str, spell_name: &str, log_time: u64, settings: &AppSettings) {
+        let start_time = self.timer_start(log_time);
+        if let Some(definition) = self.definition(spell_name, false) {
+            let duration = definition.duration_seconds(settings);
+            self.effects.apply(ActiveEffect::new(definition.name.clone(), caster.to_string(), caster.to_string(), EffectKind::Buff, start_time, duration));
+        }
+    }
+
+    /// A party member's debuff got through the target's save or spell resistance
+    pub fn track_debuff(&mut self, caster: &str, target: &str, spell_name: &str, log_time: u64, settings: &AppSettings) {
+        let start_time = self.timer_start(log_time);
+        if let Some(definition) = self.definition(spell_name, true) {
+            let duration = definition.duration_seconds(settings);
+            self.effects.apply(ActiveEffect::new(definition.name.clone(), caster.to_string(), target.to_string(), EffectKind::Debuff, start_time, duration));
+        }
     }
 
     pub fn remove_expired_buffs(&mut self) {
@@ -59,6 +266,7 @@ impl BuffTracker {
 
     pub fn clear_all_buffs(&mut self) {
         self.active_buffs.clear();
+        self.effects.clear();
     }
 
     pub fn remove_buff(&mut self, spell_name: &str) -> bool {
@@ -92,80 +300,98 @@ impl BuffTracker {
             .collect()
     }
 
-    fn calculate_buff_duration(spell_name: &str, settings: &AppSettings) -> Option<u64> {
-        let duration = match spell_name {
-            "Divine Favor" => Some(120), // Flat 2 turns
-            "Divine Might" => {
-                // Ensure minimum duration even with negative charisma modifier
-                let base_duration = (settings.charisma_modifier.max(-10) as i64 * 2 * 6).max(10) as u64;
-                if settings.extended_divine_might {
-                    Some(base_duration + 10 * 6) // Extended item adds 10 rounds
-                } else {
-                    Some(base_duration)
-                }
-            },
-            "Divine Shield" => {
-                // Ensure minimum duration even with negative charisma modifier
-                let base_duration = (settings.charisma_modifier.max(-10) as i64 * 2 * 6).max(10) as u64;
-                if settings.extended_divine_shield {
-                    Some(base_duration + 10 * 6) // Extended item adds 10 rounds
-                } else {
-                    Some(base_duration)
-                }
-            },
-            "Divine Power" => {
-                // Divine Power: 6 round per caster level * 2 (assume extended), with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some(caster_level * 6 * 2)
-            },
-            "Tenser's Transformation" => {
-                // Tenser's Transformation: 1 round per caster level * 2, with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some(caster_level * 6 * 2)
-            },
-            "Greater Sanctuary" => Some(40), // Flat 2 rounds * 2 (assume extended)
-            "Bigby's Interposing Hand" => {
-                // Bigby's Interposing Hand: 2 rounds + (1 round per caster level / 5) * 2, with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some((2 + caster_level / 2 * 6) * 2)
-            },
-            "Acid Fog" => {
-                // Acid Fog: 1 round per caster level, with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some(caster_level * 6)
-            },
-            "Cloudkill" => {
-                // Cloudkill: 1 round per caster level, with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some(caster_level * 6)
-            },
-            "Mestil's Acid Sheath" => {
-                // Mestil's Acid Sheath: 1 round per caster level * 2, with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some(caster_level * 6 * 2)
-            },
-            "Elemental Shield" => {
-                // Elemental Shield: 1 round per caster level * 2, with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some(caster_level * 6 * 2)
-            },
-            "Death Armor" => {
-                // Death Armor: 1 round per caster level * 2, with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some(caster_level * 6 * 2)
-            },
-            "Blade Thirst" => {
-                // Blade Thirst 1 round per caster level * 2, with minimum caster level of 1
-                let caster_level = settings.caster_level.max(1) as u64;
-                Some(caster_level * 6 * 2)
-            },
-            _ => None, // Unknown spells are not tracked
+    fn calculate_buff_duration(&self, spell_name: &str, settings: &AppSettings) -> Option<u64> {
+        let duration = self.definition(spell_name, false)?.duration_seconds(settings);
+        eprintln!("Buff Duration Calculation: {} - caster_level: {}, cha_mod: {}, duration: {}s",
+                 spell_name, settings.caster_level, settings.charisma_modifier, duration);
+        Some(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The durations hard-coded before buffs.json, for the buffs it knew
+    fn old_duration(spell_name: &str, caster_level: i32, charisma_modifier: i32, extended_might: bool, extended_shield: bool) -> Option<u64> {
+        let caster_level = caster_level.max(1) as u64;
+        let per_charisma = |extended: bool| {
+            let base_duration = (charisma_modifier.max(-10) as i64 * 2 * 6).max(10) as u64;
+            if extended { base_duration + 10 * 6 } else { base_duration }
         };
+        match spell_name {
+            "Divine Favor" => Some(120),
+            "Divine Might" => Some(per_charisma(extended_might)),
+            "Divine Shield" => Some(per_charisma(extended_shield)),
+            "Greater Sanctuary" => Some(40),
+            "Bigby's Interposing Hand" => Some((2 + caster_level / 2 * 6) * 2),
+            "Acid Fog" | "Cloudkill" => Some(caster_level * 6),
+            "Divine Power" | "Tenser's Transformation" | "Mestil's Acid Sheath" |
+            "Elemental Shield" | "Death Armor" | "Blade Thirst" => Some(caster_level * 6 * 2),
+            _ => None,
+        }
+    }
 
-        if let Some(d) = duration {
-            println!("Buff Duration Calculation: {} - caster_level: {}, cha_mod: {}, duration: {}s",
-                     spell_name, settings.caster_level, settings.charisma_modifier, d);
+    /// Settings as they may come from settings.json, without the options window's clamping
+    fn settings(caster_level: i32, charisma_modifier: i32, modifiers: &[&str]) -> AppSettings {
+        let mut settings = AppSettings::default();
+        settings.caster_level = caster_level;
+        settings.charisma_modifier = charisma_modifier;
+        for modifier in modifiers {
+            settings.set_buff_modifier(modifier, true);
         }
-        duration
+        settings
     }
-}
\ No newline at end of file
+
+    fn tracked_duration(tracker: &mut BuffTracker, spell_name: &str, settings: &AppSettings) -> Option<u64> {
+        tracker.clear_all_buffs();
+        tracker.add_buff(spell_name.to_string(), "Thorin".to_string(), 1_000, settings);
+        tracker.active_buffs.get(spell_name).map(|buff| buff.duration_seconds)
+    }
+
+    #[test]
+    fn defaults_match_the_old_hard_coded_durations() {
+        let mut tracker = BuffTracker::new();
+        let modifier_sets: [&[&str]; 4] = [
+            &[],
+            &["Extended Divine Might"],
+            &["Extended Divine Shield"],
+            &["Extended Divine Might", "Extended Divine Shield"],
+        ];
+        let spells: Vec<String> = default_buff_definitions().into_iter()
+            .filter(|definition| !definition.debuff)
+            .map(|definition| definition.name)
+            .collect();
+
+        for spell in &spells {
+            for caster_level in [-3, 0, 1, 2, 5, 17, 40] {
+                for charisma_modifier in [-12, -10, -1, 0, 1, 4, 12] {
+                    for modifiers in modifier_sets {
+                        let settings = settings(caster_level, charisma_modifier, modifiers);
+                        let expected = old_duration(spell, caster_level, charisma_modifier,
+                            modifiers.contains(&"Extended Divine Might"), modifiers.contains(&"Extended Divine Shield"));
+                        assert!(expected.is_some(), "{} has no old duration", spell);
+                        assert_eq!(tracked_duration(&mut tracker, spell, &settings), expected,
+                            "{} at CL {}, CHA {:+}, {:?}", spell, caster_level, charisma_modifier, modifiers);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn modifiers_only_change_their_own_buffs() {
+        let definitions = default_buff_definitions();
+        let divine_shield = definitions.iter().find(|definition| definition.name == "Divine Shield").unwrap();
+        assert_eq!(divine_shield.duration_seconds(&settings(20, 5, &["Extended Divine Might"])), 60);
+        assert_eq!(divine_shield.duration_seconds(&settings(20, 5, &["Extended Divine Shield"])), 120);
+    }
+
+    #[test]
+    fn debuffs_are_not_buffs() {
+        let tracker = BuffTracker::new();
+        assert!(tracker.is_trackable_buff("Divine Might"));
+        assert!(!tracker.is_trackable_buff("Slow"));
+        assert!(!tracker.is_trackable_buff("Bless"));
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use crate::models::damage_type::DamageType;
+use crate::models::stats::CombatantStats;
+
+/// One statistic for two combatants (or one combatant in two encounters)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonRow {
+    pub label: String,
+    pub left: Option<f64>,
+    pub right: Option<f64>,
+    /// Decimal places to show
+    pub precision: usize,
+}
+
+impl ComparisonRow {
+    fn new(label: impl Into<String>, left: Option<f64>, right: Option<f64>, precision: usize) -> Self {
+        Self { label: label.into(), left, right, precision }
+    }
+
+    fn count(label: impl Into<String>, left: u32, right: u32) -> Self {
+        Self::new(label, Some(left as f64), Some(right as f64), 0)
+    }
+
+    /// Right minus left
+    pub fn delta(&self) -> Option<f64> {
+        Some(self.right? - self.left?)
+    }
+
+    /// Change from left to right as a percentage of left
+    pub fn delta_percent(&self) -> Option<f64> {
+        let left = self.left?;
+        if left == 0.0 {
+            return None;
+        }
+        Some(self.delta()? / left.abs() * 100.0)
+    }
+
+    pub fn format_value(&self, value: Option<f64>) -> String {
+        value.map(|v| format!("{:.*}", self.precision, v)).unwrap_or_else(|| "-".to_string())
+    }
+
+    /// e.g. "+12.5 (+8%)"
+    pub fn format_delta(&self) -> String {
+        let Some(delta) = self.delta() else {
+            return "-".to_string();
+        };
+        match self.delta_percent() {
+            Some(percent) => format!("{:+.*} ({:+.0}%)", self.precision, delta, percent),
+            None => format!("{:+.*}", self.precision, delta),
+        }
+    }
+}
+
+/// Damage, DPS, hit rate, crit damage and per-type damage of two combatants side by side.
+/// Every row is a "higher is better" number.
+pub fn compare_combatants(left: &CombatantStats, right: &CombatantStats) -> Vec<ComparisonRow> {
+    let mut rows = vec![
+        ComparisonRow::count("Damage", left.total_damage_dealt, right.total_damage_dealt),
+        ComparisonRow::new("DPS", left.calculate_dps(), right.calculate_dps(), 1),
+        ComparisonRow::new("Hit rate %", left.hit_rate(), right.hit_rate(), 1),
+        ComparisonRow::count("Hits", left.hits, right.hits),
+        ComparisonRow::count("Critical hits", left.critical_hits, right.critical_hits),
+        ComparisonRow::count("Hit damage", left.hit_damage, right.hit_damage),
+        ComparisonRow::count("Crit damage", left.crit_damage, right.crit_damage),
+        ComparisonRow::new("Avg attack bonus", left.average_attack_bonus(), right.average_attack_bonus(), 1),
+    ];
+
+    // Per-type damage, biggest first
+    let mut types: HashMap<&DamageType, u32> = HashMap::new();
+    for (dtype, amount) in left.damage_by_type_dealt.iter().chain(&right.damage_by_type_dealt) {
+        *types.entry(dtype).or_default() += *amount;
+    }
+    let mut types: Vec<(&DamageType, u32)> = types.into_iter().collect();
+    types.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    for (dtype, _) in types {
+        rows.push(ComparisonRow::count(
+            format!("{} damage", dtype),
+            left.damage_by_type_dealt.get(dtype).copied().unwrap_or(0),
+            right.damage_by_type_dealt.get(dtype).copied().unwrap_or(0),
+        ));
+    }
+    rows
+}
@@ -19,6 +19,49 @@ pub struct PendingAttack {
     pub target: String,
     pub timestamp: u64,
     pub is_crit: bool,
+    pub modifiers: AttackModifiers,
+}
+
+/// The d20 roll logged after an attack result: "(15 + 57 = 72)"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttackRoll {
+    pub d20: u32,
+    pub bonus: i32,
+    pub total: i32,
+}
+
+/// The roll logged with a saving throw "(4 + 5 = 9 vs. DC: 21)" or spell
+/// resistance check "(13 + 20 = 33 vs. SR: 32)". For spell resistance the bonus
+/// is the caster's and `dc` is the target's SR.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheckRoll {
+    pub d20: u32,
+    pub bonus: i32,
+    pub total: i32,
+    pub dc: i32,
+}
+
+/// Prefixes in front of an attack line, e.g. "Off Hand : Sneak Attack : Elara attacks ..."
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AttackModifiers {
+    pub off_hand: bool,
+    pub sneak_attack: bool,
+    pub death_attack: bool,
+}
+
+impl AttackModifiers {
+    pub fn from_prefixes(prefixes: &str) -> Self {
+        let mut modifiers = Self::default();
+        for prefix in prefixes.split(':').map(str::trim) {
+            match prefix {
+                "Off Hand" => modifiers.off_hand = true,
+                "Sneak Attack" => modifiers.sneak_attack = true,
+                "Death Attack" => modifiers.death_attack = true,
+                _ => {}
+            }
+        }
+        modifiers
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -39,4 +82,11 @@ pub struct LongDurationSpell {
     pub timestamp: u64,
     pub had_save_roll: bool,
     pub had_damage_immunity: bool,
-}
\ No newline at end of file
+}
+/// A spell cast, waiting for the heal, save or spell resist lines it produces
+#[derive(Debug, Clone)]
+pub struct SpellCast {
+    pub caster: String,
+    pub spell: String,
+    pub timestamp: u64,
+}
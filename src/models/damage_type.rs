@@ -0,0 +1,160 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+/// A damage type as NWN names it in damage, immunity and absorb lines. Types a server adds
+/// (e.g. "Bludgeoning" on servers that split Physical) are kept as `Other`.
+///
+/// Serialized as its log name, so maps keyed by it stay plain JSON objects.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum DamageType {
+    Physical,
+    Magical,
+    Acid,
+    Cold,
+    Divine,
+    Electrical,
+    Fire,
+    NegativeEnergy,
+    PositiveEnergy,
+    Sonic,
+    Pure,
+    Other(String),
+}
+
+impl DamageType {
+    /// Every built-in type, for highlighting them in log text
+    pub const KNOWN: [DamageType; 11] = [
+        DamageType::Physical,
+        DamageType::Magical,
+        DamageType::Acid,
+        DamageType::Cold,
+        DamageType::Divine,
+        DamageType::Electrical,
+        DamageType::Fire,
+        DamageType::NegativeEnergy,
+        DamageType::PositiveEnergy,
+        DamageType::Sonic,
+        DamageType::Pure,
+    ];
+
+    /// Parse a type name, ignoring case. "Negative" and "Positive" (as some lines shorten
+    /// them) are the energy types.
+    pub fn from_name(name: &str) -> Self {
+        let name = name.trim();
+        match name.to_lowercase().as_str() {
+            "physical" => DamageType::Physical,
+            "magical" => DamageType::Magical,
+            "acid" => DamageType::Acid,
+            "cold" => DamageType::Cold,
+            "divine" => DamageType::Divine,
+            "electrical" => DamageType::Electrical,
+            "fire" => DamageType::Fire,
+            "negative" | "negative energy" => DamageType::NegativeEnergy,
+            "positive" | "positive energy" => DamageType::PositiveEnergy,
+            "sonic" => DamageType::Sonic,
+            "pure" => DamageType::Pure,
+            _ => DamageType::Other(name.to_string()),
+        }
+    }
+
+    /// The name NWN writes in damage lines
+    pub fn name(&self) -> &str {
+        match self {
+            DamageType::Physical => "Physical",
+            DamageType::Magical => "Magical",
+            DamageType::Acid => "Acid",
+            DamageType::Cold => "Cold",
+            DamageType::Divine => "Divine",
+            DamageType::Electrical => "Electrical",
+            DamageType::Fire => "Fire",
+            DamageType::NegativeEnergy => "Negative Energy",
+            DamageType::PositiveEnergy => "Positive Energy",
+            DamageType::Sonic => "Sonic",
+            DamageType::Pure => "Pure",
+            DamageType::Other(name) => name,
+        }
+    }
+
+    /// Display colour shared by the logs window and HTML reports; `None` for server types
+    pub fn rgb(&self) -> Option<[u8; 3]> {
+        match self {
+            DamageType::Physical => Some([220, 220, 220]),
+            DamageType::Magical => Some([147, 112, 219]),
+            DamageType::Acid => Some([34, 139, 34]),
+            DamageType::Cold => Some([135, 206, 250]),
+            DamageType::Divine => Some([255, 215, 0]),
+            DamageType::Electrical => Some([255, 255, 0]),
+            DamageType::Fire => Some([255, 69, 0]),
+            DamageType::NegativeEnergy => Some([128, 128, 128]),
+            DamageType::PositiveEnergy => Some([255, 255, 255]),
+            DamageType::Sonic => Some([255, 200, 124]),
+            DamageType::Pure => Some([255, 20, 147]),
+            DamageType::Other(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for DamageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Sorted by name, like the strings they replaced
+impl Ord for DamageType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.name().cmp(other.name())
+    }
+}
+
+impl PartialOrd for DamageType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<String> for DamageType {
+    fn from(name: String) -> Self {
+        DamageType::from_name(&name)
+    }
+}
+
+impl From<DamageType> for String {
+    fn from(dtype: DamageType) -> Self {
+        match dtype {
+            DamageType::Other(name) => name,
+            known => known.name().to_string(),
+        }
+    }
+}
+
+/// Parse the breakdown inside a damage line's parentheses, e.g. "39 Physical 5 Negative Energy".
+/// Each amount is followed by one or more words naming its type.
+pub fn parse_damage_breakdown(breakdown: &str) -> HashMap<DamageType, u32> {
+    let mut damage = HashMap::new();
+    let mut current: Option<(u32, Vec<&str>)> = None;
+
+    let mut flush = |entry: Option<(u32, Vec<&str>)>| {
+        if let Some((amount, words)) = entry
+            && !words.is_empty()
+        {
+            *damage.entry(DamageType::from_name(&words.join(" "))).or_default() += amount;
+        }
+    };
+
+    for word in breakdown.split_whitespace() {
+        match word.parse::<u32>() {
+            Ok(amount) => flush(current.replace((amount, Vec::new()))),
+            Err(_) => {
+                if let Some((_, words)) = current.as_mut() {
+                    words.push(word);
+                }
+            }
+        }
+    }
+    flush(current);
+    damage
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::models::damage_type::DamageType;
+
+/// How many damage events before a death are kept for its recap
+pub const DEATH_RECAP_EVENTS: usize = 5;
+/// Damage older than this (in seconds) before a death is left out of its recap
+pub const DEATH_RECAP_WINDOW: u64 = 10;
+
+/// One damage line taken by a combatant, kept for death recaps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamageEvent {
+    pub timestamp: u64,
+    pub attacker: String,
+    pub source: String, // "Attack", "Spell: Fireball", etc.
+    pub amount: u32,
+    pub damage_types: HashMap<DamageType, u32>,
+}
+
+impl DamageEvent {
+    /// e.g. "Thorin: 42 Attack (35 Physical, 7 Cold)"
+    pub fn describe(&self) -> String {
+        let mut types: Vec<(&DamageType, &u32)> = self.damage_types.iter().collect();
+        types.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let types: Vec<String> = types.iter().map(|(dtype, amount)| format!("{} {}", amount, dtype)).collect();
+        format!("{}: {} {} ({})", self.attacker, self.amount, self.source, types.join(", "))
+    }
+}
+
+/// A combatant dying during an encounter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeathRecord {
+    pub victim: String,
+    pub killer: String,
+    pub timestamp: u64,
+    /// The last damage the killer did to the victim, if it was logged
+    pub killing_blow: Option<DamageEvent>,
+    /// The last few damage events the victim took before dying, oldest first
+    pub recap: Vec<DamageEvent>,
+}
+
+impl DeathRecord {
+    pub fn new(victim: String, killer: String, timestamp: u64, recent_damage: Vec<DamageEvent>) -> Self {
+        let recap: Vec<DamageEvent> = recent_damage.into_iter()
+            .filter(|event| timestamp.saturating_sub(event.timestamp) <= DEATH_RECAP_WINDOW)
+            .collect();
+        let killing_blow = recap.iter().rev()
+            .find(|event| event.attacker == killer)
+            .cloned();
+        Self { victim, killer, timestamp, killing_blow, recap }
+    }
+
+    /// e.g. "Orc Warrior killed by Thorin (42 Attack)"
+    pub fn summary(&self) -> String {
+        match &self.killing_blow {
+            Some(blow) => format!("{} killed by {} ({} {})", self.victim, self.killer, blow.amount, blow.source),
+            None => format!("{} killed by {}", self.victim, self.killer),
+        }
+    }
+
+    /// Total damage in the recap
+    pub fn recap_damage(&self) -> u32 {
+        self.recap.iter().map(|event| event.amount).sum()
+    }
+}
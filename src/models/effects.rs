@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::get_current_timestamp;
+
+/// How long an expired effect stays listed, so it's clear which buffs need recasting
+pub const EXPIRED_EFFECT_LINGER: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectKind {
+    /// Cast by a party member; the log doesn't name a buff's target, so it is put on the caster
+    Buff,
+    /// Landed on an enemy by a failed save or spell resistance check
+    Debuff,
+}
+
+/// A buff or debuff on one combatant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveEffect {
+    pub name: String,
+    pub caster: String,
+    pub target: String,
+    pub kind: EffectKind,
+    pub start_time: u64,
+    pub duration_seconds: u64,
+}
+
+impl ActiveEffect {
+    pub fn new(name: String, caster: String, target: String, kind: EffectKind, start_time: u64, duration_seconds: u64) -> Self {
+        Self {
+            name,
+            caster,
+            target,
+            kind,
+            start_time,
+            duration_seconds,
+        }
+    }
+
+    pub fn remaining_seconds(&self) -> i64 {
+        let elapsed = get_current_timestamp().saturating_sub(self.start_time);
+        (self.duration_seconds as i64) - (elapsed as i64)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining_seconds() <= 0
+    }
+}
+
+/// Buffs and debuffs per target, for every combatant rather than just the main character
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EffectTracker {
+    effects: Vec<ActiveEffect>,
+}
+
+impl EffectTracker {
+    /// Add an effect, replacing the same effect already on that target
+    pub fn apply(&mut self, effect: ActiveEffect) {
+        // Rebuilding from the log applies plenty of effects that ran out long ago
+        self.remove_stale();
+        self.effects.retain(|e| !(e.target == effect.target && e.name.eq_ignore_ascii_case(&effect.name)));
+        self.effects.push(effect);
+    }
+
+    pub fn remove(&mut self, target: &str, name: &str) -> bool {
+        let before = self.effects.len();
+        self.effects.retain(|e| !(e.target == target && e.name.eq_ignore_ascii_case(name)));
+        self.effects.len() != before
+    }
+
+    /// Take back an effect applied at or after `since` (a save that followed a failed
+    /// spell resistance check)
+    pub fn remove_recent(&mut self, target: &str, name: &str, since: u64) {
+        self.effects.retain(|e| {
+            !(e.target == target && e.name.eq_ignore_ascii_case(name) && e.start_time >= since)
+        });
+    }
+
+    /// Drop everything on a combatant, e.g. when it dies
+    pub fn remove_target(&mut self, target: &str) {
+        self.effects.retain(|e| e.target != target);
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    /// Forget effects that ran out more than `EXPIRED_EFFECT_LINGER` seconds ago
+    pub fn remove_stale(&mut self) {
+        self.effects.retain(|e| e.remaining_seconds() > -(EXPIRED_EFFECT_LINGER as i64));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Effects grouped by target (sorted by name), soonest to expire first
+    pub fn by_target(&self) -> Vec<(&str, Vec<&ActiveEffect>)> {
+        let mut targets: Vec<&str> = self.effects.iter().map(|e| e.target.as_str()).collect();
+        targets.sort_unstable();
+        targets.dedup();
+        targets.into_iter()
+            .map(|target| {
+                let mut effects: Vec<&ActiveEffect> = self.effects.iter().filter(|e| e.target == target).collect();
+                effects.sort_by_key(|e| e.remaining_seconds());
+                (target, effects)
+            })
+            .collect()
+    }
+}
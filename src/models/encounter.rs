@@ -1,8 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
 use crate::models::stats::CombatantStats;
-use crate::utils::time::format_duration;
+use crate::models::death::DeathRecord;
+use crate::utils::time::{format_clock_time, format_date_time, format_duration};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Encounter {
     pub id: u64,
     pub start_time: u64,
@@ -10,6 +12,8 @@ pub struct Encounter {
     pub stats: HashMap<String, CombatantStats>,
     pub most_damaged_participant: String,
     pub total_damage: u32,
+    #[serde(default)]
+    pub deaths: Vec<DeathRecord>,
 }
 
 impl Encounter {
@@ -21,6 +25,7 @@ impl Encounter {
             stats: HashMap::new(),
             most_damaged_participant: String::new(),
             total_damage: 0,
+            deaths: Vec::new(),
         }
     }
 
@@ -59,6 +64,21 @@ impl Encounter {
         }
     }
 
+    /// Wall-clock start and end, e.g. "2025-07-29 14:10:26 - 14:11:31"
+    pub fn time_range(&self) -> String {
+        let start = format_date_time(self.start_time);
+        let end = format_date_time(self.end_time);
+        match (start.split_once(' '), end.split_once(' ')) {
+            (Some((start_day, _)), Some((end_day, end_clock))) if start_day == end_day => format!("{} - {}", start, end_clock),
+            _ => format!("{} - {}", start, end),
+        }
+    }
+
+    /// Wall-clock start time, e.g. "14:10:26"
+    pub fn start_clock_time(&self) -> String {
+        format_clock_time(self.start_time)
+    }
+
     pub fn get_display_name(&self) -> String {
         let duration_str = format_duration(self.duration());
         if self.most_damaged_participant.is_empty() {
@@ -67,4 +87,50 @@ impl Encounter {
             format!("#{} {} {}", self.id, duration_str, self.most_damaged_participant)
         }
     }
-}
\ No newline at end of file
+}
+/// Encounter boundaries set by hand, applied when the log is parsed
+#[derive(Debug, Clone, Default)]
+pub struct EncounterEdits {
+    /// Timestamps that always start a new encounter
+    pub splits: BTreeSet<u64>,
+    /// Time ranges (start, end) that always stay a single encounter
+    pub merges: Vec<(u64, u64)>,
+}
+
+impl EncounterEdits {
+    /// Start a new encounter at `timestamp`, undoing any merge that spans it
+    pub fn split_at(&mut self, timestamp: u64) {
+        self.merges.retain(|&(start, end)| timestamp <= start || timestamp > end);
+        self.splits.insert(timestamp);
+    }
+
+    /// Keep everything from `start` to `end` in one encounter, undoing splits inside it
+    pub fn merge_range(&mut self, start: u64, end: u64) {
+        self.splits.retain(|&split| split <= start || split > end);
+        self.merges.push((start, end));
+    }
+
+    /// Whether a split point falls after `encounter_start` and at or before `timestamp`
+    pub fn split_due(&self, encounter_start: u64, timestamp: u64) -> bool {
+        timestamp > encounter_start && self.splits.range(encounter_start + 1..=timestamp).next().is_some()
+    }
+
+    /// Whether an encounter that started at `encounter_start` must still be going at `timestamp`
+    pub fn holds_together(&self, encounter_start: u64, timestamp: u64) -> bool {
+        self.merges.iter().any(|&(start, end)| start <= encounter_start && timestamp <= end)
+    }
+}
+
+/// Merge the per-combatant stats of several encounters, as the "Encounters" multi-select view does
+pub fn combine_encounter_stats<'a, I>(encounters: I) -> HashMap<String, CombatantStats>
+where
+    I: IntoIterator<Item = &'a Encounter>,
+{
+    let mut combined: HashMap<String, CombatantStats> = HashMap::new();
+    for encounter in encounters {
+        for (name, stats) in &encounter.stats {
+            combined.entry(name.clone()).or_default().merge(stats);
+        }
+    }
+    combined
+}
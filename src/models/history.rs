@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::models::encounter::Encounter;
+
+/// One finished encounter kept in the history store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// File name of the log the encounter was read from, e.g. "nwclientLog1.txt"
+    pub log_file: String,
+    pub encounter: Encounter,
+}
+
+impl HistoryEntry {
+    fn overlaps(&self, other: &HistoryEntry) -> bool {
+        self.log_file == other.log_file
+            && self.encounter.start_time <= other.encounter.end_time
+            && other.encounter.start_time <= self.encounter.end_time
+    }
+}
+
+/// What recording an encounter did to the history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryChange {
+    /// Already stored exactly like this
+    Unchanged,
+    /// A new encounter; it can be appended to the store
+    Added,
+    /// Replaced older versions of the same fight (after a reparse); the store must be rewritten
+    Replaced,
+}
+
+/// Encounters from past sessions, keyed by log file and start time
+#[derive(Debug, Clone, Default)]
+pub struct EncounterHistory {
+    entries: BTreeMap<(u64, String), HistoryEntry>,
+    /// Log file currently being read, for encounters recorded without one
+    pub active_log: Option<String>,
+}
+
+impl EncounterHistory {
+    pub fn from_entries(entries: impl IntoIterator<Item = HistoryEntry>) -> Self {
+        let mut history = Self::default();
+        for entry in entries {
+            history.record(entry);
+        }
+        history
+    }
+
+    /// Store a finished encounter. Earlier versions of the same fight from the same log
+    /// (e.g. before it was split or merged) are dropped.
+    pub fn record(&mut self, entry: HistoryEntry) -> HistoryChange {
+        let key = (entry.encounter.start_time, entry.log_file.clone());
+        if let Some(existing) = self.entries.get(&key)
+            && same_encounter(&existing.encounter, &entry.encounter)
+        {
+            return HistoryChange::Unchanged;
+        }
+
+        let before = self.entries.len();
+        self.entries.retain(|_, existing| !existing.overlaps(&entry));
+        let replaced = self.entries.len() < before;
+        self.entries.insert(key, entry);
+        if replaced { HistoryChange::Replaced } else { HistoryChange::Added }
+    }
+
+    /// Newest first
+    pub fn entries(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.values().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Encounters have no `PartialEq`, so compare what would be written to disk.
+/// IDs are left out since they depend on where parsing of the log started.
+fn same_encounter(a: &Encounter, b: &Encounter) -> bool {
+    let without_id = |encounter: &Encounter| {
+        serde_json::to_value(encounter).ok().map(|mut value| {
+            if let Some(fields) = value.as_object_mut() {
+                fields.remove("id");
+            }
+            value
+        })
+    };
+    match (without_id(a), without_id(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
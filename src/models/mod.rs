@@ -4,15 +4,25 @@ pub mod context;
 pub mod player;
 pub mod settings;
 pub mod buffs;
+pub mod timeline;
+pub mod death;
+pub mod profile;
+pub mod history;
+pub mod comparison;
+pub mod pets;
+pub mod damage_type;
+pub mod effects;
+pub mod alerts;
 
-pub use stats::CombatantStats;
-pub use encounter::Encounter;
-pub use context::{ViewMode, SpellContext, PendingAttack, PendingSpell, LongDurationSpell};
+pub use stats::{CombatantStats, AcBounds, CheckStats, SaveSummary};
+pub use encounter::{Encounter, EncounterEdits, combine_encounter_stats};
+pub use context::{ViewMode, SpellContext, PendingAttack, AttackRoll, AttackModifiers, CheckRoll, PendingSpell, LongDurationSpell, SpellCast};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DamageViewMode {
     DamageDone,
     DamageTaken,
+    Healing,
 }
 
 impl Default for DamageViewMode {
@@ -34,5 +44,14 @@ impl Default for CombatantFilter {
     }
 }
 pub use player::PlayerRegistry;
-pub use settings::AppSettings;
-pub use buffs::BuffTracker;
\ No newline at end of file
+pub use settings::{AppSettings, CharacterProfile, LogRetention, RetentionAction};
+pub use buffs::{BuffTracker, BuffDefinition, BuffModifier};
+pub use timeline::{DamageTimeline, TimelineSeries, ROLLING_DPS_WINDOW};
+pub use death::{DamageEvent, DeathRecord, DEATH_RECAP_EVENTS};
+pub use profile::{TargetProfile, build_target_profiles};
+pub use history::{EncounterHistory, HistoryEntry, HistoryChange};
+pub use comparison::{ComparisonRow, compare_combatants};
+pub use pets::{PetNameRule, PetNameRules, is_summon_spell};
+pub use damage_type::{DamageType, parse_damage_breakdown};
+pub use effects::{ActiveEffect, EffectKind, EffectTracker, EXPIRED_EFFECT_LINGER};
+pub use alerts::{Alert, AlertEvent, AlertQueue, AlertRule, AlertTrigger};
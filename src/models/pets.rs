@@ -0,0 +1,69 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Spells whose caster gets a creature fighting on their side (matched as part of the spell name,
+/// so "Summon" covers Summon Creature I-IX, Summon Familiar, Summon Animal Companion and so on)
+const SUMMON_SPELLS: &[&str] = &[
+    "Summon",
+    "Gate",
+    "Planar Binding",
+    "Planar Ally",
+    "Animate Dead",
+    "Create Undead",
+    "Create Greater Undead",
+    "Elemental Swarm",
+    "Mordenkainen's Sword",
+    "Black Blade of Disaster",
+    "Shelgarn's Persistent Blade",
+];
+
+pub fn is_summon_spell(spell: &str) -> bool {
+    SUMMON_SPELLS.iter().any(|summon| spell.contains(summon))
+}
+
+/// A user rule that names a pet's owner, e.g. pattern `^(?P<owner>.+)'s Hawk$` with owner
+/// `$owner`, or pattern `^Tomi Undergallows$` with owner `Aria` for a henchman
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PetNameRule {
+    /// Regex matched against combatant names
+    pub pattern: String,
+    /// The owner's name; `$name` or `${name}` inserts a capture group from the pattern
+    pub owner: String,
+}
+
+/// Pet name rules compiled for matching; recompiled only when the rules change
+#[derive(Debug, Clone, Default)]
+pub struct PetNameRules {
+    rules: Vec<PetNameRule>,
+    compiled: Vec<(Regex, String)>,
+}
+
+impl PetNameRules {
+    pub fn update(&mut self, rules: &[PetNameRule]) {
+        if self.rules == rules {
+            return;
+        }
+        self.rules = rules.to_vec();
+        self.compiled = rules.iter()
+            .filter(|rule| !rule.pattern.trim().is_empty())
+            .filter_map(|rule| match Regex::new(&rule.pattern) {
+                Ok(regex) => Some((regex, rule.owner.clone())),
+                Err(e) => {
+                    eprintln!("Ignoring pet name rule '{}': {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+    }
+
+    /// Owner named by the first rule that matches
+    pub fn owner_of(&self, name: &str) -> Option<String> {
+        self.compiled.iter().find_map(|(regex, owner)| {
+            let captures = regex.captures(name)?;
+            let mut expanded = String::new();
+            captures.expand(owner, &mut expanded);
+            let expanded = expanded.trim();
+            (!expanded.is_empty() && expanded != name).then(|| expanded.to_string())
+        })
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use crate::models::pets::PetNameRules;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerData {
@@ -12,6 +13,12 @@ pub struct PlayerRegistry {
     pub players: HashMap<String, PlayerData>, // account_name -> PlayerData
     pub character_to_account: HashMap<String, String>, // character_name -> account_name
     pub main_player_account: Option<String>, // The main player (first "has joined as a player")
+    /// Summons and companions learned from this session's summon casts: pet name -> owner
+    #[serde(skip)]
+    pub pet_owners: HashMap<String, String>,
+    /// The user's pet name rules from the settings
+    #[serde(skip)]
+    pub pet_name_rules: PetNameRules,
 }
 
 impl PlayerRegistry {
@@ -39,7 +46,7 @@ impl PlayerRegistry {
         let temp_account = format!("player_{}", character_name);
         if temp_account != account_name && self.players.contains_key(&temp_account) {
             self.players.remove(&temp_account);
-            println!("Cleaned up temporary account '{}' for character '{}'", temp_account, character_name);
+            eprintln!("Cleaned up temporary account '{}' for character '{}'", temp_account, character_name);
         }
 
         // Ensure player exists
@@ -76,6 +83,21 @@ impl PlayerRegistry {
         self.character_to_account.contains_key(name)
     }
 
+    pub fn add_pet(&mut self, pet_name: String, owner_name: String) {
+        self.pet_owners.insert(pet_name, owner_name);
+    }
+
+    /// Owner of a pet learned from a summon cast or named by a pet name rule
+    pub fn pet_owner(&self, name: &str) -> Option<String> {
+        self.pet_owners.get(name).cloned()
+            .or_else(|| self.pet_name_rules.owner_of(name))
+    }
+
+    /// Players, their pets and "Player | Summon" combatants
+    pub fn is_friendly(&self, name: &str) -> bool {
+        self.is_player(name) || name.contains(" | ") || self.pet_owner(name).is_some()
+    }
+
     pub fn get_main_player_info(&self) -> Option<(String, String)> {
         if let Some(account) = &self.main_player_account {
             if let Some(player) = self.players.get(account) {
@@ -122,7 +144,7 @@ impl PlayerRegistry {
                 if real_account != &temp_account {
                     // We have a real account, so remove the temporary one
                     self.players.remove(&temp_account);
-                    println!("Cleaned up temporary account '{}' - character '{}' is now under account '{}'",
+                    eprintln!("Cleaned up temporary account '{}' - character '{}' is now under account '{}'",
                              temp_account, character_name, real_account);
                 }
             }
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use crate::models::encounter::Encounter;
+use crate::models::damage_type::DamageType;
+use crate::models::stats::{AcBounds, SaveSummary};
+
+/// What the logs reveal about one creature name across every encounter it appeared in
+#[derive(Debug, Clone, Default)]
+pub struct TargetProfile {
+    pub name: String,
+    pub encounters: u32,
+    /// AC range from everyone's attack rolls against this name
+    pub ac: AcBounds,
+    /// Saves and spell resistance checks this name made against everyone's spells
+    pub saves: SaveSummary,
+    pub damage_by_type_received: HashMap<DamageType, u32>,
+    pub absorbed_by_type: HashMap<DamageType, u32>,
+    pub absorbed_by_resistance: u32,
+    pub absorbed_by_reduction: u32,
+}
+
+impl TargetProfile {
+    /// Share of each damage type absorbed by Damage Immunity, highest first
+    pub fn immunity_percentages(&self) -> Vec<(DamageType, f64)> {
+        let mut immunities: Vec<(DamageType, f64)> = self.absorbed_by_type.iter()
+            .map(|(dtype, absorbed)| {
+                let taken = self.damage_by_type_received.get(dtype).copied().unwrap_or(0);
+                let total = taken + absorbed;
+                let percent = if total > 0 { *absorbed as f64 / total as f64 * 100.0 } else { 0.0 };
+                (dtype.clone(), percent)
+            })
+            .collect();
+        immunities.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        immunities
+    }
+
+    /// Damage soaked by Damage Resistance and Damage Reduction
+    pub fn resistance_and_reduction(&self) -> (u32, u32) {
+        (self.absorbed_by_resistance, self.absorbed_by_reduction)
+    }
+}
+
+/// Build a profile per combatant name from every given encounter
+pub fn build_target_profiles<'a, I>(encounters: I) -> HashMap<String, TargetProfile>
+where
+    I: IntoIterator<Item = &'a Encounter>,
+{
+    let mut profiles: HashMap<String, TargetProfile> = HashMap::new();
+    for encounter in encounters {
+        for (name, stats) in &encounter.stats {
+            let profile = profiles.entry(name.clone()).or_insert_with(|| TargetProfile {
+                name: name.clone(),
+                ..Default::default()
+            });
+            profile.encounters += 1;
+            profile.saves.merge(&stats.saves_against);
+            for (dtype, amount) in &stats.damage_by_type_received {
+                *profile.damage_by_type_received.entry(dtype.clone()).or_default() += *amount;
+            }
+            for (dtype, amount) in &stats.absorbed_by_type {
+                *profile.absorbed_by_type.entry(dtype.clone()).or_default() += *amount;
+            }
+            profile.absorbed_by_resistance += stats.absorbed_by_resistance;
+            profile.absorbed_by_reduction += stats.absorbed_by_reduction;
+        }
+
+        // AC comes from the attackers' side
+        for stats in encounter.stats.values() {
+            for (target, bounds) in &stats.ac_by_target {
+                profiles.entry(target.clone())
+                    .or_insert_with(|| TargetProfile { name: target.clone(), ..Default::default() })
+                    .ac.merge(bounds);
+            }
+        }
+    }
+    profiles
+}
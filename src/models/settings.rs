@@ -1,4 +1,7 @@
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
+use crate::models::alerts::AlertRule;
+use crate::models::pets::PetNameRule;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
@@ -6,16 +9,105 @@ pub struct AppSettings {
     pub caster_level: i32,
     /// Charisma modifier for spell calculations (-10 to +50)
     pub charisma_modifier: i32,
-    /// Whether Extended Divine Might is active
-    pub extended_divine_might: bool,
-    /// Whether Extended Divine Shield is active
-    pub extended_divine_shield: bool,
+    /// Feats and items from buffs.json that the character has (e.g. "Extended Divine Might")
+    #[serde(default)]
+    pub buff_modifiers: Vec<String>,
+    /// Toggles from before buffs.json; moved into `buff_modifiers` when settings load
+    #[serde(default, skip_serializing)]
+    extended_divine_might: bool,
+    #[serde(default, skip_serializing)]
+    extended_divine_shield: bool,
+    /// Main character whose profile the caster level, charisma and buff modifiers above belong to
+    #[serde(default)]
+    pub active_character: Option<String>,
+    /// Buff-duration build values per character, swapped in when the main player switches character
+    #[serde(default)]
+    pub character_profiles: BTreeMap<String, CharacterProfile>,
     /// Warning time for expiring buffs in seconds (1-30)
     pub buff_warning_seconds: u32,
+    /// Save running buff timers on exit and restore them on the next start
+    #[serde(default)]
+    pub persist_buff_timers: bool,
     /// Custom log directory path (None for auto-detection)
     pub log_directory: Option<String>,
     /// Buff window position (x, y)
     pub buff_window_pos: Option<(f32, f32)>,
+    /// Seconds without combat before the next hit starts a new encounter (2-60)
+    #[serde(default = "default_encounter_timeout")]
+    pub encounter_timeout: u64,
+    /// End the encounter when its most damaged enemy dies
+    #[serde(default)]
+    pub end_on_primary_target_death: bool,
+    /// Let an "Initiative Roll" line start a new encounter, not just damage
+    #[serde(default)]
+    pub start_on_initiative: bool,
+    /// Resting always ends the current encounter
+    #[serde(default = "default_true")]
+    pub rest_ends_encounter: bool,
+    /// What to do with old nwclientLog*.txt files (nothing unless enabled)
+    #[serde(default)]
+    pub log_retention: LogRetention,
+    /// Count damage done by summons, companions and henchmen as their owner's
+    #[serde(default)]
+    pub merge_pet_damage: bool,
+    /// Name patterns for pets whose owner can't be learned from a summon cast
+    #[serde(default)]
+    pub pet_name_rules: Vec<PetNameRule>,
+    /// Sounds and flashes for buff timers and live combat events
+    #[serde(default)]
+    pub alert_rules: Vec<AlertRule>,
+}
+
+/// The build values buff durations depend on, saved for one character
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterProfile {
+    pub caster_level: i32,
+    pub charisma_modifier: i32,
+    #[serde(default)]
+    pub buff_modifiers: Vec<String>,
+}
+
+/// What happens to log files that fall outside the retention limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RetentionAction {
+    /// Move into `archive/<date>/` inside the log directory
+    #[default]
+    Archive,
+    Delete,
+}
+
+/// Opt-in limits for old log files. A file is removed (or archived) when it breaks any
+/// enabled limit; the newest log in each directory is never touched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRetention {
+    pub enabled: bool,
+    pub action: RetentionAction,
+    /// Logs last written more than this many days ago
+    pub max_age_days: Option<u32>,
+    /// Logs beyond the newest N in a directory
+    pub max_files: Option<u32>,
+    /// Logs beyond the newest ones that add up to this many megabytes in a directory
+    pub max_total_mb: Option<u64>,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            action: RetentionAction::Archive,
+            max_age_days: Some(30),
+            max_files: None,
+            max_total_mb: None,
+        }
+    }
+}
+
+fn default_encounter_timeout() -> u64 {
+    6
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Default for AppSettings {
@@ -23,11 +115,23 @@ impl Default for AppSettings {
         Self {
             caster_level: 1,
             charisma_modifier: 0,
+            buff_modifiers: Vec::new(),
             extended_divine_might: false,
             extended_divine_shield: false,
+            active_character: None,
+            character_profiles: BTreeMap::new(),
             buff_warning_seconds: 10,
+            persist_buff_timers: false,
             log_directory: None,
             buff_window_pos: None,
+            encounter_timeout: default_encounter_timeout(),
+            end_on_primary_target_death: false,
+            start_on_initiative: false,
+            rest_ends_encounter: true,
+            log_retention: LogRetention::default(),
+            merge_pet_damage: false,
+            pet_name_rules: Vec::new(),
+            alert_rules: Vec::new(),
         }
     }
 }
@@ -36,15 +140,157 @@ impl AppSettings {
     /// Clamps caster level to valid range (1-40)
     pub fn set_caster_level(&mut self, level: i32) {
         self.caster_level = level.clamp(1, 40);
+        self.store_active_profile();
     }
 
     /// Clamps charisma modifier to reasonable range (-10 to +50)
     pub fn set_charisma_modifier(&mut self, modifier: i32) {
         self.charisma_modifier = modifier.clamp(-10, 50);
+        self.store_active_profile();
+    }
+
+    /// Turn a buff modifier from buffs.json on or off
+    pub fn set_buff_modifier(&mut self, name: &str, enabled: bool) {
+        self.buff_modifiers.retain(|modifier| modifier != name);
+        if enabled {
+            self.buff_modifiers.push(name.to_string());
+        }
+        self.store_active_profile();
+    }
+
+    /// Save the current build values as the active character's profile
+    fn store_active_profile(&mut self) {
+        if let Some(character) = &self.active_character {
+            self.character_profiles.insert(character.clone(), CharacterProfile {
+                caster_level: self.caster_level,
+                charisma_modifier: self.charisma_modifier,
+                buff_modifiers: self.buff_modifiers.clone(),
+            });
+        }
+    }
+
+    /// Make `character` the active character, loading their saved profile. A character
+    /// without one starts from the current values. Returns false if they were already active.
+    pub fn switch_character(&mut self, character: &str) -> bool {
+        if self.active_character.as_deref() == Some(character) {
+            return false;
+        }
+        self.store_active_profile();
+        self.active_character = Some(character.to_string());
+        match self.character_profiles.get(character).cloned() {
+            Some(profile) => {
+                self.caster_level = profile.caster_level;
+                self.charisma_modifier = profile.charisma_modifier;
+                self.buff_modifiers = profile.buff_modifiers;
+            }
+            None => self.store_active_profile(),
+        }
+        true
+    }
+
+    /// Forget a character's profile (the active character keeps the current values)
+    pub fn remove_character_profile(&mut self, character: &str) {
+        self.character_profiles.remove(character);
+        if self.active_character.as_deref() == Some(character) {
+            self.active_character = None;
+        }
+    }
+
+    /// Carry the old Extended Divine Might/Shield toggles over to `buff_modifiers`
+    pub fn migrate_legacy_buff_toggles(&mut self) {
+        for (enabled, name) in [
+            (std::mem::take(&mut self.extended_divine_might), "Extended Divine Might"),
+            (std::mem::take(&mut self.extended_divine_shield), "Extended Divine Shield"),
+        ] {
+            if enabled && !self.buff_modifiers.iter().any(|modifier| modifier == name) {
+                self.buff_modifiers.push(name.to_string());
+            }
+        }
     }
 
     /// Clamps buff warning seconds to valid range (1-30)
     pub fn set_buff_warning_seconds(&mut self, seconds: u32) {
         self.buff_warning_seconds = seconds.clamp(1, 30);
     }
-}
\ No newline at end of file
+
+    /// Clamps the encounter timeout to valid range (2-60)
+    pub fn set_encounter_timeout(&mut self, seconds: u64) {
+        self.encounter_timeout = seconds.clamp(2, 60);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings_for(character: &str, caster_level: i32, charisma_modifier: i32, modifiers: &[&str]) -> AppSettings {
+        let mut settings = AppSettings::default();
+        settings.switch_character(character);
+        settings.set_caster_level(caster_level);
+        settings.set_charisma_modifier(charisma_modifier);
+        for modifier in modifiers {
+            settings.set_buff_modifier(modifier, true);
+        }
+        settings
+    }
+
+    #[test]
+    fn first_character_takes_the_current_values() {
+        let mut settings = AppSettings::default();
+        settings.set_caster_level(17);
+        assert!(settings.switch_character("Champion"));
+        assert_eq!(settings.active_character.as_deref(), Some("Champion"));
+        assert_eq!(settings.caster_level, 17);
+        assert_eq!(settings.character_profiles["Champion"].caster_level, 17);
+    }
+
+    #[test]
+    fn switching_back_restores_each_characters_values() {
+        let mut settings = settings_for("Champion", 20, 6, &["Extended Divine Might"]);
+
+        // A new character starts from the current values and then diverges
+        assert!(settings.switch_character("Cleric"));
+        settings.set_caster_level(30);
+        settings.set_charisma_modifier(2);
+        settings.set_buff_modifier("Extended Divine Might", false);
+
+        assert!(settings.switch_character("Champion"));
+        assert_eq!((settings.caster_level, settings.charisma_modifier), (20, 6));
+        assert_eq!(settings.buff_modifiers, vec!["Extended Divine Might".to_string()]);
+
+        assert!(settings.switch_character("Cleric"));
+        assert_eq!((settings.caster_level, settings.charisma_modifier), (30, 2));
+        assert!(settings.buff_modifiers.is_empty());
+    }
+
+    #[test]
+    fn switching_to_the_active_character_does_nothing() {
+        let mut settings = settings_for("Champion", 20, 6, &[]);
+        assert!(!settings.switch_character("Champion"));
+        assert_eq!(settings.character_profiles.len(), 1);
+    }
+
+    #[test]
+    fn removing_the_active_profile_keeps_the_current_values() {
+        let mut settings = settings_for("Champion", 20, 6, &[]);
+        settings.remove_character_profile("Champion");
+        assert_eq!(settings.active_character, None);
+        assert!(settings.character_profiles.is_empty());
+        assert_eq!(settings.caster_level, 20);
+
+        // Without an active character, edits are not saved to any profile
+        settings.set_caster_level(5);
+        assert!(settings.character_profiles.is_empty());
+    }
+
+    #[test]
+    fn profiles_survive_a_settings_round_trip() {
+        let mut settings = settings_for("Champion", 20, 6, &["Extended Divine Might"]);
+        settings.switch_character("Cleric");
+        let json = serde_json::to_string(&settings).unwrap();
+        let mut loaded: AppSettings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.character_profiles, settings.character_profiles);
+        assert!(loaded.switch_character("Champion"));
+        assert_eq!(loaded.caster_level, 20);
+    }
+}
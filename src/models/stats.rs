@@ -1,6 +1,11 @@
 use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::models::context::CheckRoll;
+use crate::models::damage_type::DamageType;
+use crate::models::timeline::DamageTimeline;
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CombatantStats {
     // --- Stats for actions performed by the combatant ---
     pub hits: u32,
@@ -12,56 +17,288 @@ pub struct CombatantStats {
     pub hit_damage: u32,
     pub crit_damage: u32,
     pub weapon_buff_damage: u32,
-    pub damage_by_type_dealt: HashMap<String, u32>,
-    pub hit_damage_by_type: HashMap<String, u32>,
-    pub crit_damage_by_type: HashMap<String, u32>,
-    pub weapon_buff_damage_by_type: HashMap<String, u32>,
+    pub damage_by_type_dealt: HashMap<DamageType, u32>,
+    pub hit_damage_by_type: HashMap<DamageType, u32>,
+    pub crit_damage_by_type: HashMap<DamageType, u32>,
+    pub weapon_buff_damage_by_type: HashMap<DamageType, u32>,
     pub damage_by_source_dealt: HashMap<String, u32>, // "Attack", "Spell: Fireball", etc.
-    pub damage_by_source_and_type_dealt: HashMap<String, HashMap<String, u32>>, // Source -> Type -> Amount
+    pub damage_by_source_and_type_dealt: HashMap<String, HashMap<DamageType, u32>>, // Source -> Type -> Amount
     pub damage_by_target_dealt: HashMap<String, u32>, // Target -> Total damage to that target
     pub damage_by_target_and_source_dealt: HashMap<String, HashMap<String, u32>>, // Target -> Source -> Amount
-    pub damage_by_target_source_and_type_dealt: HashMap<String, HashMap<String, HashMap<String, u32>>>, // Target -> Source -> Type -> Amount
-    pub hit_damage_by_target_type: HashMap<String, HashMap<String, u32>>, // Target -> Type -> Amount (for hit damage only)
-    pub crit_damage_by_target_type: HashMap<String, HashMap<String, u32>>, // Target -> Type -> Amount (for crit damage only)
-    pub weapon_buff_damage_by_target_type: HashMap<String, HashMap<String, u32>>, // Target -> Type -> Amount (for weapon buff damage only)
+    pub damage_by_target_source_and_type_dealt: HashMap<String, HashMap<String, HashMap<DamageType, u32>>>, // Target -> Source -> Type -> Amount
+    pub hit_damage_by_target_type: HashMap<String, HashMap<DamageType, u32>>, // Target -> Type -> Amount (for hit damage only)
+    pub crit_damage_by_target_type: HashMap<String, HashMap<DamageType, u32>>, // Target -> Type -> Amount (for crit damage only)
+    pub weapon_buff_damage_by_target_type: HashMap<String, HashMap<DamageType, u32>>, // Target -> Type -> Amount (for weapon buff damage only)
+
+    // --- Attack rolls (only attacks that logged their d20 roll) ---
+    pub attack_rolls: u32,
+    pub attack_bonus_sum: i64,
+    pub max_attack_bonus: Option<i32>,
+    pub natural_ones: u32,
+    pub natural_twenties: u32,
+    pub sneak_attacks: u32, // Hits with a "Sneak Attack" prefix
+    pub sneak_attack_damage: u32,
+    pub death_attacks: u32, // Hits with a "Death Attack" prefix
+    pub death_attack_damage: u32,
+    pub off_hand_hits: u32,
+    pub off_hand_damage: u32,
+    pub ac_by_target: HashMap<String, AcBounds>, // Target -> AC bounds seen from this combatant's rolls
 
     // --- Stats for actions received by the combatant ---
     pub times_attacked: u32,
     pub total_damage_received: u32,
-    pub damage_by_type_received: HashMap<String, u32>,
+    pub damage_by_type_received: HashMap<DamageType, u32>,
     pub damage_by_source_received: HashMap<String, u32>, // Track who/what damaged this combatant
-    pub damage_by_source_and_type_received: HashMap<String, HashMap<String, u32>>, // Source -> Type -> Amount
+    pub damage_by_source_and_type_received: HashMap<String, HashMap<DamageType, u32>>, // Source -> Type -> Amount
     pub damage_by_attacker_received: HashMap<String, u32>, // Attacker -> Total damage from that attacker
     pub damage_by_attacker_and_source_received: HashMap<String, HashMap<String, u32>>, // Attacker -> Source -> Amount
 
+    // --- Saving throws and spell resistance ---
+    pub saves_against: SaveSummary, // Rolls this combatant made against other casters' spells
+    pub saves_forced: SaveSummary, // Rolls others made against this combatant's spells
+
     // --- Special stats like absorption ---
     pub total_damage_absorbed: u32,
-    pub absorbed_by_type: HashMap<String, u32>,
+    pub absorbed_by_type: HashMap<DamageType, u32>, // Damage Immunity only
+    pub absorbed_by_resistance: u32, // Damage Resistance (untyped in the log)
+    pub absorbed_by_reduction: u32, // Damage Reduction (untyped in the log)
+
+    // --- Healing ---
+    // Effective/overheal split is an estimate based on damage taken since the last rest
+    pub healing_done: u32,
+    pub effective_healing_done: u32,
+    pub overhealing_done: u32,
+    pub healing_by_target_done: HashMap<String, u32>, // Target -> Amount healed
+    pub healing_by_source_done: HashMap<String, u32>, // "Spell: Heal", "Unknown", etc.
+    pub healing_received: u32,
+    pub overhealing_received: u32,
+    pub healing_by_healer_received: HashMap<String, u32>, // Healer -> Amount
+
+    // --- Deaths ---
+    pub kills: u32,
+    pub deaths: u32,
     
     // --- Timing for DPS calculation ---
     pub first_action_time: Option<u64>,
     pub last_action_time: Option<u64>,
+
+    // --- Per-second damage for DPS graphs ---
+    pub timeline: DamageTimeline,
 }
 
-impl CombatantStats {
-    pub fn calculate_dps(&self) -> Option<f64> {
-        if let (Some(first), Some(last)) = (self.first_action_time, self.last_action_time) {
-            let duration_secs = if last > first { last - first } else { 1 };
-            if duration_secs > 0 && self.total_damage_dealt > 0 {
-                Some(self.total_damage_dealt as f64 / duration_secs as f64)
-            } else {
-                None
+/// What a combatant's attack rolls reveal about a target's AC.
+/// A total that hits means AC <= total, one that misses means AC > total (natural 1s and 20s are ignored).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AcBounds {
+    pub highest_miss: Option<i32>,
+    pub lowest_hit: Option<i32>,
+}
+
+impl AcBounds {
+    pub fn record_hit(&mut self, total: i32) {
+        self.lowest_hit = Some(self.lowest_hit.map_or(total, |hit| hit.min(total)));
+    }
+
+    pub fn record_miss(&mut self, total: i32) {
+        self.highest_miss = Some(self.highest_miss.map_or(total, |miss| miss.max(total)));
+    }
+
+    pub fn merge(&mut self, other: &AcBounds) {
+        if let Some(hit) = other.lowest_hit {
+            self.record_hit(hit);
+        }
+        if let Some(miss) = other.highest_miss {
+            self.record_miss(miss);
+        }
+    }
+
+    /// e.g. "61-72", "<= 72" or ">= 61"
+    pub fn describe(&self) -> String {
+        match (self.highest_miss, self.lowest_hit) {
+            (Some(miss), Some(hit)) if miss < hit => format!("{}-{}", miss + 1, hit),
+            (Some(miss), Some(hit)) => format!("{}-{}?", hit, miss + 1), // AC changed during the fight
+            (None, Some(hit)) => format!("<= {}", hit),
+            (Some(miss), None) => format!(">= {}", miss + 1),
+            (None, None) => "?".to_string(),
+        }
+    }
+}
+
+/// Results of saving throws or spell resistance checks, and the rolls seen with them.
+/// For spell resistance `passed` means the spell was resisted and the DC is the target's SR.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckStats {
+    pub passed: u32,
+    pub failed: u32,
+    pub min_bonus: Option<i32>,
+    pub max_bonus: Option<i32>,
+    pub min_dc: Option<i32>,
+    pub max_dc: Option<i32>,
+}
+
+impl CheckStats {
+    pub fn record(&mut self, passed: bool, roll: Option<CheckRoll>) {
+        if passed {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+        }
+        if let Some(roll) = roll {
+            self.record_range(Some(roll.bonus), Some(roll.bonus), Some(roll.dc), Some(roll.dc));
+        }
+    }
+
+    pub fn merge(&mut self, other: &CheckStats) {
+        self.passed += other.passed;
+        self.failed += other.failed;
+        self.record_range(other.min_bonus, other.max_bonus, other.min_dc, other.max_dc);
+    }
+
+    fn record_range(&mut self, min_bonus: Option<i32>, max_bonus: Option<i32>, min_dc: Option<i32>, max_dc: Option<i32>) {
+        self.min_bonus = min_option(self.min_bonus, min_bonus);
+        self.max_bonus = max_option(self.max_bonus, max_bonus);
+        self.min_dc = min_option(self.min_dc, min_dc);
+        self.max_dc = max_option(self.max_dc, max_dc);
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.passed + self.failed
+    }
+
+    /// Percentage of checks passed
+    pub fn pass_rate(&self) -> Option<f64> {
+        if self.attempts() > 0 {
+            Some(self.passed as f64 / self.attempts() as f64 * 100.0)
+        } else {
+            None
+        }
+    }
+
+    /// e.g. "+12" or "+10..+14", empty if no rolls were logged
+    pub fn bonus_range(&self) -> String {
+        format_range(self.min_bonus, self.max_bonus, true)
+    }
+
+    /// e.g. "21" or "18..24", empty if no rolls were logged
+    pub fn dc_range(&self) -> String {
+        format_range(self.min_dc, self.max_dc, false)
+    }
+}
+
+/// Saving throws and spell resistance checks from one side (the target or the caster).
+/// `passed` always means the target saved or resisted.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveSummary {
+    pub saves_by_type: HashMap<String, CheckStats>, // "Fortitude"/"Reflex"/"Will" -> results
+    pub saves_by_element: HashMap<String, CheckStats>, // "Fire", "Mind Spells", ... -> results
+    pub saves_by_opponent: HashMap<String, CheckStats>, // Caster (for targets) or target (for casters) -> results
+    pub spell_resist_by_spell: HashMap<String, CheckStats>, // Spell -> resisted/failed
+}
+
+impl SaveSummary {
+    pub fn record_save(&mut self, save_type: &str, element: &str, opponent: Option<&str>, passed: bool, roll: Option<CheckRoll>) {
+        self.saves_by_type.entry(save_type.to_string()).or_default().record(passed, roll);
+        self.saves_by_element.entry(element.to_string()).or_default().record(passed, roll);
+        if let Some(opponent) = opponent {
+            self.saves_by_opponent.entry(opponent.to_string()).or_default().record(passed, roll);
+        }
+    }
+
+    pub fn record_spell_resist(&mut self, spell: &str, passed: bool, roll: Option<CheckRoll>) {
+        self.spell_resist_by_spell.entry(spell.to_string()).or_default().record(passed, roll);
+    }
+
+    pub fn merge(&mut self, other: &SaveSummary) {
+        for (mine, theirs) in [
+            (&mut self.saves_by_type, &other.saves_by_type),
+            (&mut self.saves_by_element, &other.saves_by_element),
+            (&mut self.saves_by_opponent, &other.saves_by_opponent),
+            (&mut self.spell_resist_by_spell, &other.spell_resist_by_spell),
+        ] {
+            for (key, checks) in theirs {
+                mine.entry(key.clone()).or_default().merge(checks);
             }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.saves_by_type.is_empty() && self.spell_resist_by_spell.is_empty()
+    }
+
+    /// All saving throws combined
+    pub fn total_saves(&self) -> CheckStats {
+        let mut total = CheckStats::default();
+        for checks in self.saves_by_type.values() {
+            total.merge(checks);
+        }
+        total
+    }
+}
+
+fn min_option(a: Option<i32>, b: Option<i32>) -> Option<i32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn max_option(a: Option<i32>, b: Option<i32>) -> Option<i32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn format_range(min: Option<i32>, max: Option<i32>, signed: bool) -> String {
+    let format_value = |value: i32| if signed { format!("{:+}", value) } else { value.to_string() };
+    match (min, max) {
+        (Some(min), Some(max)) if min == max => format_value(min),
+        (Some(min), Some(max)) => format!("{}..{}", format_value(min), format_value(max)),
+        _ => String::new(),
+    }
+}
+
+impl CombatantStats {
+    /// Average attack bonus over the attacks that logged a roll
+    pub fn average_attack_bonus(&self) -> Option<f64> {
+        if self.attack_rolls > 0 {
+            Some(self.attack_bonus_sum as f64 / self.attack_rolls as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Share of attacks that hit (critical hits included), as a percentage
+    pub fn hit_rate(&self) -> Option<f64> {
+        let attempts = self.hits + self.critical_hits + self.misses;
+        if attempts > 0 {
+            Some((self.hits + self.critical_hits) as f64 / attempts as f64 * 100.0)
         } else {
             None
         }
     }
 
+    pub fn calculate_dps(&self) -> Option<f64> {
+        self.rate(self.total_damage_dealt)
+    }
+
     pub fn calculate_dtps(&self) -> Option<f64> {
+        self.rate(self.total_damage_received)
+    }
+
+    pub fn calculate_hps(&self) -> Option<f64> {
+        self.rate(self.healing_done)
+    }
+
+    /// `amount` per second between this combatant's first and last action (at least one second)
+    fn rate(&self, amount: u32) -> Option<f64> {
         if let (Some(first), Some(last)) = (self.first_action_time, self.last_action_time) {
             let duration_secs = if last > first { last - first } else { 1 };
-            if duration_secs > 0 && self.total_damage_received > 0 {
-                Some(self.total_damage_received as f64 / duration_secs as f64)
+            if duration_secs > 0 && amount > 0 {
+                Some(amount as f64 / duration_secs as f64)
             } else {
                 None
             }
@@ -76,4 +313,187 @@ impl CombatantStats {
         }
         self.last_action_time = Some(timestamp);
     }
-}
\ No newline at end of file
+
+    /// Add another set of stats (e.g. the same combatant in a different encounter) into this one
+    pub fn merge(&mut self, source: &CombatantStats) {
+        // Aggregate all stats
+        self.hits += source.hits;
+        self.misses += source.misses;
+        self.critical_hits += source.critical_hits;
+        self.concealment_dodges += source.concealment_dodges;
+        self.weapon_buffs += source.weapon_buffs;
+        self.total_damage_dealt += source.total_damage_dealt;
+        self.hit_damage += source.hit_damage;
+        self.crit_damage += source.crit_damage;
+        self.weapon_buff_damage += source.weapon_buff_damage;
+        self.times_attacked += source.times_attacked;
+        self.total_damage_received += source.total_damage_received;
+        self.total_damage_absorbed += source.total_damage_absorbed;
+        self.absorbed_by_resistance += source.absorbed_by_resistance;
+        self.absorbed_by_reduction += source.absorbed_by_reduction;
+        self.healing_done += source.healing_done;
+        self.effective_healing_done += source.effective_healing_done;
+        self.overhealing_done += source.overhealing_done;
+        self.healing_received += source.healing_received;
+        self.overhealing_received += source.overhealing_received;
+        self.attack_rolls += source.attack_rolls;
+        self.attack_bonus_sum += source.attack_bonus_sum;
+        if let Some(bonus) = source.max_attack_bonus {
+            self.max_attack_bonus = Some(self.max_attack_bonus.map_or(bonus, |existing| existing.max(bonus)));
+        }
+        self.natural_ones += source.natural_ones;
+        self.natural_twenties += source.natural_twenties;
+        self.sneak_attacks += source.sneak_attacks;
+        self.sneak_attack_damage += source.sneak_attack_damage;
+        self.death_attacks += source.death_attacks;
+        self.death_attack_damage += source.death_attack_damage;
+        self.off_hand_hits += source.off_hand_hits;
+        self.off_hand_damage += source.off_hand_damage;
+        self.kills += source.kills;
+        self.deaths += source.deaths;
+        
+        // Aggregate damage by type dealt
+        for (dtype, amount) in &source.damage_by_type_dealt {
+            *self.damage_by_type_dealt.entry(dtype.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate hit damage by type
+        for (dtype, amount) in &source.hit_damage_by_type {
+            *self.hit_damage_by_type.entry(dtype.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate crit damage by type
+        for (dtype, amount) in &source.crit_damage_by_type {
+            *self.crit_damage_by_type.entry(dtype.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate weapon buff damage by type
+        for (dtype, amount) in &source.weapon_buff_damage_by_type {
+            *self.weapon_buff_damage_by_type.entry(dtype.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate damage sources
+        for (source_name, amount) in &source.damage_by_source_dealt {
+            *self.damage_by_source_dealt.entry(source_name.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate damage by source and type dealt
+        for (source_name, type_map) in &source.damage_by_source_and_type_dealt {
+            let target_type_map = self.damage_by_source_and_type_dealt.entry(source_name.clone()).or_default();
+            for (dtype, amount) in type_map {
+                *target_type_map.entry(dtype.clone()).or_default() += *amount;
+            }
+        }
+        
+        // Aggregate damage by target
+        for (target_name, amount) in &source.damage_by_target_dealt {
+            *self.damage_by_target_dealt.entry(target_name.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate damage by target and source dealt
+        for (target_name, source_map) in &source.damage_by_target_and_source_dealt {
+            let target_map = self.damage_by_target_and_source_dealt.entry(target_name.clone()).or_default();
+            for (source_name, amount) in source_map {
+                *target_map.entry(source_name.clone()).or_default() += *amount;
+            }
+        }
+        
+        // Aggregate damage by target, source, and type dealt
+        for (target_name, source_map) in &source.damage_by_target_source_and_type_dealt {
+            let target_map = self.damage_by_target_source_and_type_dealt.entry(target_name.clone()).or_default();
+            for (source_name, type_map) in source_map {
+                let source_map = target_map.entry(source_name.clone()).or_default();
+                for (dtype, amount) in type_map {
+                    *source_map.entry(dtype.clone()).or_default() += *amount;
+                }
+            }
+        }
+        
+        // Aggregate hit damage by target and type
+        for (target_name, type_map) in &source.hit_damage_by_target_type {
+            let target_map = self.hit_damage_by_target_type.entry(target_name.clone()).or_default();
+            for (dtype, amount) in type_map {
+                *target_map.entry(dtype.clone()).or_default() += *amount;
+            }
+        }
+        
+        // Aggregate crit damage by target and type
+        for (target_name, type_map) in &source.crit_damage_by_target_type {
+            let target_map = self.crit_damage_by_target_type.entry(target_name.clone()).or_default();
+            for (dtype, amount) in type_map {
+                *target_map.entry(dtype.clone()).or_default() += *amount;
+            }
+        }
+        
+        // Aggregate weapon buff damage by target and type
+        for (target_name, type_map) in &source.weapon_buff_damage_by_target_type {
+            let target_map = self.weapon_buff_damage_by_target_type.entry(target_name.clone()).or_default();
+            for (dtype, amount) in type_map {
+                *target_map.entry(dtype.clone()).or_default() += *amount;
+            }
+        }
+        
+        // Aggregate damage sources received
+        for (source_name, amount) in &source.damage_by_source_received {
+            *self.damage_by_source_received.entry(source_name.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate damage by source and type received
+        for (source_name, type_map) in &source.damage_by_source_and_type_received {
+            let target_source_map = self.damage_by_source_and_type_received.entry(source_name.clone()).or_default();
+            for (dtype, amount) in type_map {
+                *target_source_map.entry(dtype.clone()).or_default() += *amount;
+            }
+        }
+        
+        // Aggregate damage by attacker received
+        for (attacker, amount) in &source.damage_by_attacker_received {
+            *self.damage_by_attacker_received.entry(attacker.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate damage by attacker and source received
+        for (attacker, source_map) in &source.damage_by_attacker_and_source_received {
+            let target_attacker_map = self.damage_by_attacker_and_source_received.entry(attacker.clone()).or_default();
+            for (source_name, amount) in source_map {
+                *target_attacker_map.entry(source_name.clone()).or_default() += *amount;
+            }
+        }
+        
+        // Aggregate absorbed damage by type
+        for (dtype, amount) in &source.absorbed_by_type {
+            *self.absorbed_by_type.entry(dtype.clone()).or_default() += *amount;
+        }
+        
+        // Aggregate healing maps
+        for (target_name, amount) in &source.healing_by_target_done {
+            *self.healing_by_target_done.entry(target_name.clone()).or_default() += *amount;
+        }
+        for (source_name, amount) in &source.healing_by_source_done {
+            *self.healing_by_source_done.entry(source_name.clone()).or_default() += *amount;
+        }
+        for (healer, amount) in &source.healing_by_healer_received {
+            *self.healing_by_healer_received.entry(healer.clone()).or_default() += *amount;
+        }
+
+        self.saves_against.merge(&source.saves_against);
+        self.saves_forced.merge(&source.saves_forced);
+
+        for (target_name, bounds) in &source.ac_by_target {
+            self.ac_by_target.entry(target_name.clone()).or_default().merge(bounds);
+        }
+
+        self.timeline.merge(&source.timeline);
+
+        // Update timing for combined stats
+        if let Some(first) = source.first_action_time {
+            self.first_action_time = Some(
+                self.first_action_time.map_or(first, |existing| existing.min(first))
+            );
+        }
+        if let Some(last) = source.last_action_time {
+            self.last_action_time = Some(
+                self.last_action_time.map_or(last, |existing| existing.max(last))
+            );
+        }
+    }
+}
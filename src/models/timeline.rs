@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+/// Width of the window used for rolling DPS, in seconds
+pub const ROLLING_DPS_WINDOW: u64 = 5;
+
+/// Damage bucketed per log second, so bursts and downtime within a fight can be plotted
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DamageTimeline {
+    pub dealt: BTreeMap<u64, u32>, // Timestamp -> Damage dealt in that second
+    pub taken: BTreeMap<u64, u32>, // Timestamp -> Damage taken in that second
+}
+
+/// Which side of a timeline to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineSeries {
+    Dealt,
+    Taken,
+}
+
+impl DamageTimeline {
+    pub fn record_dealt(&mut self, timestamp: u64, amount: u32) {
+        *self.dealt.entry(timestamp).or_default() += amount;
+    }
+
+    pub fn record_taken(&mut self, timestamp: u64, amount: u32) {
+        *self.taken.entry(timestamp).or_default() += amount;
+    }
+
+    pub fn merge(&mut self, other: &DamageTimeline) {
+        for (timestamp, amount) in &other.dealt {
+            *self.dealt.entry(*timestamp).or_default() += amount;
+        }
+        for (timestamp, amount) in &other.taken {
+            *self.taken.entry(*timestamp).or_default() += amount;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dealt.is_empty() && self.taken.is_empty()
+    }
+
+    fn series(&self, series: TimelineSeries) -> &BTreeMap<u64, u32> {
+        match series {
+            TimelineSeries::Dealt => &self.dealt,
+            TimelineSeries::Taken => &self.taken,
+        }
+    }
+
+    /// First and last second with any damage in either direction
+    pub fn time_range(&self) -> Option<(u64, u64)> {
+        let first = self.dealt.keys().next().into_iter().chain(self.taken.keys().next()).min()?;
+        let last = self.dealt.keys().next_back().into_iter().chain(self.taken.keys().next_back()).max()?;
+        Some((*first, *last))
+    }
+
+    /// Damage per second averaged over the trailing `window` seconds, for every second in `start..=end`
+    pub fn rolling_dps(&self, series: TimelineSeries, window: u64, start: u64, end: u64) -> Vec<(u64, f64)> {
+        let window = window.max(1);
+        let buckets = self.series(series);
+        (start..=end)
+            .map(|t| {
+                let from = t.saturating_sub(window - 1);
+                let sum: u32 = buckets.range(from..=t).map(|(_, amount)| amount).sum();
+                (t, sum as f64 / window as f64)
+            })
+            .collect()
+    }
+
+    /// `rolling_dps` averaged over buckets of `bucket` seconds, one point per bucket at its first second
+    pub fn bucketed_rolling_dps(&self, series: TimelineSeries, window: u64, start: u64, end: u64, bucket: u64) -> Vec<(u64, f64)> {
+        self.rolling_dps(series, window, start, end)
+            .chunks(bucket.max(1) as usize)
+            .map(|chunk| (chunk[0].0, chunk.iter().map(|(_, dps)| dps).sum::<f64>() / chunk.len() as f64))
+            .collect()
+    }
+}
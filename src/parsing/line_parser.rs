@@ -1,16 +1,19 @@
 use std::collections::HashMap;
+use crate::models::{AttackRoll, AttackModifiers, CheckRoll, DamageType, parse_damage_breakdown};
 use crate::parsing::regex::*;
-use crate::utils::time::{parse_timestamp, get_current_timestamp};
+use crate::utils::time::{LogClock, get_current_timestamp};
 
 #[derive(Debug)]
 pub enum ParsedLine {
-    Attack { attacker: String, target: String, result: String, concealment: bool, timestamp: u64 },
-    Damage { attacker: String, target: String, total: u32, breakdown: HashMap<String, u32>, timestamp: u64 },
-    Absorb { target: String, amount: u32, dtype: String, timestamp: u64 },
+    /// `concealment` is the target's concealment percentage if the line shows one
+    Attack { attacker: String, target: String, result: String, concealment: Option<u32>, roll: Option<AttackRoll>, modifiers: AttackModifiers, timestamp: u64 },
+    Damage { attacker: String, target: String, total: u32, breakdown: HashMap<DamageType, u32>, timestamp: u64 },
+    Absorb { target: String, amount: u32, dtype: DamageType, timestamp: u64 },
     AbsorbResistance { target: String, amount: u32, timestamp: u64 },
     AbsorbReduction { target: String, amount: u32, timestamp: u64 },
-    SpellResist { target: String, spell: String, result: String, timestamp: u64 },
-    Save { target: String, save_type: String, element: String, result: String, timestamp: u64 },
+    SpellResist { target: String, spell: String, result: String, roll: Option<CheckRoll>, timestamp: u64 },
+    /// `save_type` is "Fortitude", "Reflex" or "Will" (without the " Save" suffix)
+    Save { target: String, save_type: String, element: String, result: String, roll: Option<CheckRoll>, timestamp: u64 },
     Casting { caster: String, spell: String, timestamp: u64 },
     Casts { caster: String, spell: String, timestamp: u64 },
     PlayerJoin { account_name: String, timestamp: u64 },
@@ -19,6 +22,36 @@ pub enum ParsedLine {
     PartyJoin { character_name: String, timestamp: u64 },
     Resting { timestamp: u64 },
     BuffExpired { spell_name: String, timestamp: u64 },
+    /// `healer` is only known for the negative-damage form of heal lines
+    Heal { healer: Option<String>, target: String, amount: u32, timestamp: u64 },
+    Death { killer: String, victim: String, timestamp: u64 },
+    Initiative { character: String, timestamp: u64 },
+}
+
+impl ParsedLine {
+    /// Timestamp of the log line this event was parsed from
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            ParsedLine::Attack { timestamp, .. } => *timestamp,
+            ParsedLine::Damage { timestamp, .. } => *timestamp,
+            ParsedLine::Absorb { timestamp, .. } => *timestamp,
+            ParsedLine::AbsorbResistance { timestamp, .. } => *timestamp,
+            ParsedLine::AbsorbReduction { timestamp, .. } => *timestamp,
+            ParsedLine::SpellResist { timestamp, .. } => *timestamp,
+            ParsedLine::Save { timestamp, .. } => *timestamp,
+            ParsedLine::Casting { timestamp, .. } => *timestamp,
+            ParsedLine::Casts { timestamp, .. } => *timestamp,
+            ParsedLine::PlayerJoin { timestamp, .. } => *timestamp,
+            ParsedLine::PlayerChat { timestamp, .. } => *timestamp,
+            ParsedLine::PartyChat { timestamp, .. } => *timestamp,
+            ParsedLine::PartyJoin { timestamp, .. } => *timestamp,
+            ParsedLine::Resting { timestamp, .. } => *timestamp,
+            ParsedLine::BuffExpired { timestamp, .. } => *timestamp,
+            ParsedLine::Heal { timestamp, .. } => *timestamp,
+            ParsedLine::Death { timestamp, .. } => *timestamp,
+            ParsedLine::Initiative { timestamp, .. } => *timestamp,
+        }
+    }
 }
 
 pub fn is_long_duration_spell(spell: &str) -> bool {
@@ -31,21 +64,44 @@ pub fn is_long_duration_spell(spell: &str) -> bool {
     )
 }
 
-pub fn get_spell_damage_type(spell: &str) -> Option<&'static str> {
+pub fn is_healing_spell(spell: &str) -> bool {
+    spell.starts_with("Cure ") ||
+    spell.starts_with("Mass Cure ") ||
+    matches!(spell,
+        "Heal" |
+        "Mass Heal" |
+        "Healing Circle" |
+        "Lay on Hands" |
+        "Lay On Hands" |
+        "Regenerate" |
+        "Monstrous Regeneration"
+    )
+}
+
+pub fn get_spell_damage_type(spell: &str) -> Option<DamageType> {
     match spell {
-        "Flame Arrow" => Some("Fire"),
-        "Ball Lightning" => Some("Electrical"),
-        "Isaac's Greater Missile Storm" | "Isaac's Lesser Missile Storm" | "Magic Missile" => Some("Magical"),
+        "Flame Arrow" => Some(DamageType::Fire),
+        "Ball Lightning" => Some(DamageType::Electrical),
+        "Isaac's Greater Missile Storm" | "Isaac's Lesser Missile Storm" | "Magic Missile" => Some(DamageType::Magical),
         _ => None,
     }
 }
 
-pub fn parse_log_line(line: &str) -> Option<ParsedLine> {
-    let timestamp = if let Some(caps) = RE_TIMESTAMP.captures(line) {
-        parse_timestamp(&caps[1])
-    } else {
-        get_current_timestamp() // Fallback to current time if no timestamp
-    };
+/// The optional "(d20 + bonus = total vs. DC: n)" part of a save or spell resist line
+fn check_roll(caps: &regex::Captures) -> Option<CheckRoll> {
+    Some(CheckRoll {
+        d20: caps.name("d20")?.as_str().parse().ok()?,
+        bonus: caps.name("bonus")?.as_str().parse().ok()?,
+        total: caps.name("total")?.as_str().parse().ok()?,
+        dc: caps.name("dc")?.as_str().parse().ok()?,
+    })
+}
+
+/// Parse one log line, dating it with `clock` (Unix seconds)
+pub fn parse_log_line(line: &str, clock: &mut LogClock) -> Option<ParsedLine> {
+    let timestamp = RE_TIMESTAMP.captures(line)
+        .and_then(|caps| clock.timestamp(&caps[1]))
+        .unwrap_or_else(get_current_timestamp); // Fallback to current time if no timestamp
 
     let clean_line = line.trim().strip_prefix("[CHAT WINDOW TEXT]").and_then(|s| s.splitn(2, ']').nth(1)).unwrap_or(line).trim();
 
@@ -98,6 +154,7 @@ pub fn parse_log_line(line: &str) -> Option<ParsedLine> {
             target: caps["target"].trim().to_string(),
             spell: caps["spell"].trim().to_string(),
             result: caps["result"].to_string(),
+            roll: check_roll(&caps),
             timestamp,
         });
     }
@@ -105,9 +162,10 @@ pub fn parse_log_line(line: &str) -> Option<ParsedLine> {
     if let Some(caps) = RE_SAVE.captures(clean_line) {
         return Some(ParsedLine::Save {
             target: caps["target"].trim().to_string(),
-            save_type: caps["save_type"].trim().to_string(),
+            save_type: caps["save_type"].trim().trim_end_matches(" Save").to_string(),
             element: caps["element"].trim().to_string(),
             result: caps["result"].to_string(),
+            roll: check_roll(&caps),
             timestamp,
         });
     }
@@ -129,12 +187,21 @@ pub fn parse_log_line(line: &str) -> Option<ParsedLine> {
     }
 
     if let Some(caps) = RE_ATTACK.captures(clean_line) {
-        let concealment = caps.name("concealment").is_some();
+        let roll = match (caps.name("d20"), caps.name("bonus"), caps.name("total")) {
+            (Some(d20), Some(bonus), Some(total)) => Some(AttackRoll {
+                d20: d20.as_str().parse().unwrap_or(0),
+                bonus: bonus.as_str().parse().unwrap_or(0),
+                total: total.as_str().parse().unwrap_or(0),
+            }),
+            _ => None,
+        };
         return Some(ParsedLine::Attack {
             attacker: caps["attacker"].trim().to_string(),
             target: caps["target"].trim().to_string(),
             result: caps["result"].to_string(),
-            concealment,
+            concealment: caps.name("concealment").and_then(|c| c.as_str().parse().ok()),
+            roll,
+            modifiers: AttackModifiers::from_prefixes(&caps["prefixes"]),
             timestamp,
         });
     }
@@ -145,24 +212,37 @@ pub fn parse_log_line(line: &str) -> Option<ParsedLine> {
             attacker: caps["attacker"].trim().to_string(),
             target: caps["target"].trim().to_string(),
             result: "miss".to_string(),
-            concealment: true,
+            concealment: caps["concealment"].parse().ok(),
+            roll: None,
+            modifiers: AttackModifiers::from_prefixes(&caps["prefixes"]),
+            timestamp,
+        });
+    }
+
+    if let Some(caps) = RE_HEAL_DAMAGE.captures(clean_line) {
+        return Some(ParsedLine::Heal {
+            healer: Some(caps["healer"].trim().to_string()),
+            target: caps["target"].trim().to_string(),
+            amount: caps["amount"].parse().unwrap_or(0),
+            timestamp,
+        });
+    }
+
+    if let Some(caps) = RE_HEALED.captures(clean_line) {
+        return Some(ParsedLine::Heal {
+            healer: None,
+            target: caps["character"].trim().to_string(),
+            amount: caps["amount"].parse().unwrap_or(0),
             timestamp,
         });
     }
 
     if let Some(caps) = RE_DAMAGE.captures(clean_line) {
-        let mut damage_breakdown = HashMap::new();
-        let parts = caps["breakdown"].split_whitespace().collect::<Vec<_>>();
-        for chunk in parts.chunks(2) {
-            if let (Ok(amount), Some(dtype)) = (chunk[0].parse::<u32>(), chunk.get(1)) {
-                damage_breakdown.insert(dtype.to_string(), amount);
-            }
-        }
         return Some(ParsedLine::Damage {
             attacker: caps["attacker"].trim().to_string(),
             target: caps["target"].trim().to_string(),
             total: caps["total"].parse().unwrap_or(0),
-            breakdown: damage_breakdown,
+            breakdown: parse_damage_breakdown(&caps["breakdown"]),
             timestamp,
         });
     }
@@ -171,7 +251,7 @@ pub fn parse_log_line(line: &str) -> Option<ParsedLine> {
         return Some(ParsedLine::Absorb {
             target: caps["target"].trim().to_string(),
             amount: caps["amount"].parse().unwrap_or(0),
-            dtype: caps["type"].to_string(),
+            dtype: DamageType::from_name(&caps["type"]),
             timestamp,
         });
     }
@@ -192,5 +272,20 @@ pub fn parse_log_line(line: &str) -> Option<ParsedLine> {
         });
     }
 
+    if let Some(caps) = RE_INITIATIVE.captures(clean_line) {
+        return Some(ParsedLine::Initiative {
+            character: caps["character"].trim().to_string(),
+            timestamp,
+        });
+    }
+
+    if let Some(caps) = RE_KILLED.captures(clean_line) {
+        return Some(ParsedLine::Death {
+            killer: caps["killer"].trim().to_string(),
+            victim: caps["victim"].trim().to_string(),
+            timestamp,
+        });
+    }
+
     None
 }
\ No newline at end of file
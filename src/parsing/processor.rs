@@ -1,37 +1,56 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use crate::models::{Encounter, SpellContext, PendingAttack, PendingSpell, LongDurationSpell, PlayerRegistry, BuffTracker, AppSettings};
-use crate::parsing::line_parser::{ParsedLine, is_long_duration_spell, get_spell_damage_type};
-use crate::utils::auto_save_player_registry;
-
-pub fn process_parsed_line(
-    parsed: ParsedLine,
-    combat_time: u64,
-    last_combat_time: &mut u64,
-    current_encounter: &mut Option<u64>,
-    spell_contexts: &mut Vec<SpellContext>,
-    pending_attacks: &mut Vec<PendingAttack>,
-    pending_spells: &mut Vec<PendingSpell>,
-    long_duration_spells: &mut Vec<LongDurationSpell>,
-    encounters: &Arc<Mutex<HashMap<u64, Encounter>>>,
-    encounter_counter: &Arc<Mutex<u64>>,
-    player_registry: &Arc<Mutex<PlayerRegistry>>,
-    buff_tracker: &Arc<Mutex<BuffTracker>>,
-    settings: &AppSettings,
-    is_historical: bool
-) {
-    const ENCOUNTER_TIMEOUT: u64 = 6;
+use std::sync::Mutex;
+use crate::models::{Encounter, SpellContext, PendingAttack, AttackModifiers, PendingSpell, LongDurationSpell, SpellCast, DamageEvent, DeathRecord, PlayerRegistry, DEATH_RECAP_EVENTS, AppSettings, BuffTracker, DamageType, is_summon_spell};
+use crate::parsing::line_parser::{ParsedLine, is_long_duration_spell, is_healing_spell, get_spell_damage_type};
+use crate::session::{CombatSession, CombatState};
+use crate::utils::{auto_save_player_registry, auto_save_app_settings};
+
+/// Apply one parsed line to the session's encounters, registry and buff tracker
+pub fn process_parsed_line(session: &mut CombatSession, parsed: ParsedLine, is_historical: bool) {
+    let combat_time = parsed.timestamp();
+    let settings = &session.current_settings();
+    let persist_registry = session.persist_player_registry;
+    let shared_settings = &session.settings;
+    let persist_settings = session.persist_settings;
+    let encounters = &session.encounters;
+    let encounter_counter = &session.encounter_counter;
+    let player_registry = &session.player_registry;
+    let buff_tracker = &session.buff_tracker;
+    let encounter_edits = &session.encounter_edits;
+    let CombatState {
+        last_combat_time,
+        current_encounter,
+        spell_contexts,
+        pending_attacks,
+        pending_spells,
+        long_duration_spells,
+        pending_heals,
+        recent_casts,
+        pending_summons,
+        health_deficits,
+        recent_damage,
+        dead_combatants,
+        encounter_closed,
+        close_pending,
+    } = &mut session.state;
+
+    // How long after a healing spell cast its heal lines are still attributed to it
+    const HEAL_CAST_WINDOW: u64 = 3;
+    // How long after a cast its save and spell resist lines are still attributed to it
+    const SPELL_CAST_WINDOW: u64 = 6;
+    // How long after a summon spell the creature it brought may first join the fight
+    const SUMMON_WINDOW: u64 = 30;
 
     // Handle player identification events first (these don't start encounters)
     match &parsed {
         ParsedLine::PlayerJoin { account_name, .. } => {
             if let Ok(mut registry) = player_registry.lock() {
-                println!("PlayerJoin detected: account '{}', current main_player_account: {:?}",
+                eprintln!("PlayerJoin detected: account '{}', current main_player_account: {:?}",
                         account_name, registry.main_player_account);
 
                 // Check if this is a re-login of the main player
                 let is_re_login = registry.main_player_account.as_ref() == Some(account_name);
-                println!("Is re-login: {}", is_re_login);
+                eprintln!("Is re-login: {}", is_re_login);
 
                 if is_re_login {
                     // Get character names before clearing
@@ -40,7 +59,7 @@ pub fn process_parsed_line(
                     } else {
                         Vec::new()
                     };
-                    println!("Character names before clearing: {:?}", chars_before);
+                    eprintln!("Character names before clearing: {:?}", chars_before);
 
                     // Clear character names for re-login
                     registry.clear_character_names(account_name);
@@ -50,52 +69,61 @@ pub fn process_parsed_line(
                         let buffs_before = tracker.active_buffs.len();
                         tracker.clear_all_buffs();
                         let buffs_after = tracker.active_buffs.len();
-                        println!("Cleared {} buffs (had {}, now {})", buffs_before, buffs_before, buffs_after);
+                        eprintln!("Cleared {} buffs (had {}, now {})", buffs_before, buffs_before, buffs_after);
                     }
-                    println!("Main player {} re-logged in - cleared character data and buffs", account_name);
+                    eprintln!("Main player {} re-logged in - cleared character data and buffs", account_name);
                 } else {
-                    println!("Different player joined (not main player): {}", account_name);
+                    eprintln!("Different player joined (not main player): {}", account_name);
                 }
 
                 registry.add_player_join(account_name.clone());
-                auto_save_player_registry(&registry);
+                if persist_registry {
+                    auto_save_player_registry(&registry);
+                }
             }
             return;
         }
         ParsedLine::PlayerChat { account_name, character_name, .. } => {
+            let mut main_character = None;
             if let Ok(mut registry) = player_registry.lock() {
                 // Check if this is the main player account with a different character
                 if registry.main_player_account.as_ref() == Some(account_name) {
+                    main_character = Some(character_name.clone());
                     // Get the current main character
                     let current_main_character = registry.get_main_player_info().map(|(_, char)| char);
 
-                    println!("Main player chat detected: account '{}', character '{}', current main character: {:?}",
+                    eprintln!("Main player chat detected: account '{}', character '{}', current main character: {:?}",
                             account_name, character_name, current_main_character);
 
                     // Check if this is a different character than the current one
                     if let Some(current_char) = current_main_character {
                         if current_char != *character_name {
-                            println!("Character switch detected! From '{}' to '{}'", current_char, character_name);
+                            eprintln!("Character switch detected! From '{}' to '{}'", current_char, character_name);
 
                             // Clear all buffs since it's a character switch
                             if let Ok(mut tracker) = buff_tracker.lock() {
                                 let buffs_before = tracker.active_buffs.len();
                                 tracker.clear_all_buffs();
-                                println!("Cleared {} buffs due to character switch", buffs_before);
+                                eprintln!("Cleared {} buffs due to character switch", buffs_before);
                             }
 
                             // Clear character names and re-add the new one
                             registry.clear_character_names(account_name);
-                            println!("Cleared previous character associations for main player");
+                            eprintln!("Cleared previous character associations for main player");
                         }
                     } else {
-                        println!("First character detected for main player: '{}'", character_name);
+                        eprintln!("First character detected for main player: '{}'", character_name);
                     }
                 }
 
                 registry.add_character_name(account_name.clone(), character_name.clone());
-                auto_save_player_registry(&registry);
-                println!("Associated character '{}' with account '{}'", character_name, account_name);
+                if persist_registry {
+                    auto_save_player_registry(&registry);
+                }
+                eprintln!("Associated character '{}' with account '{}'", character_name, account_name);
+            }
+            if let Some(character) = main_character {
+                activate_character_profile(shared_settings, &character, persist_settings);
             }
             return;
         }
@@ -105,16 +133,22 @@ pub fn process_parsed_line(
                 if let Some(main_account) = registry.main_player_account.clone() {
                     if let Some(player) = registry.players.get(&main_account) {
                         if player.character_names.is_empty() {
-                            println!("Main player has no characters - assuming party chat from '{}' is main player", character_name);
+                            eprintln!("Main player has no characters - assuming party chat from '{}' is main player", character_name);
                             registry.add_character_name(main_account, character_name.clone());
-                            auto_save_player_registry(&registry);
+                            if persist_registry {
+                                auto_save_player_registry(&registry);
+                            }
+                            drop(registry);
+                            activate_character_profile(shared_settings, character_name, persist_settings);
                             return;
                         }
                     }
                 }
                 registry.add_party_member(character_name.clone());
-                auto_save_player_registry(&registry);
-                println!("Detected player from party chat: {}", character_name);
+                if persist_registry {
+                    auto_save_player_registry(&registry);
+                }
+                eprintln!("Detected player from party chat: {}", character_name);
             }
             return;
         }
@@ -124,16 +158,22 @@ pub fn process_parsed_line(
                 if let Some(main_account) = registry.main_player_account.clone() {
                     if let Some(player) = registry.players.get(&main_account) {
                         if player.character_names.is_empty() {
-                            println!("Main player has no characters - assuming party join from '{}' is main player", character_name);
+                            eprintln!("Main player has no characters - assuming party join from '{}' is main player", character_name);
                             registry.add_character_name(main_account, character_name.clone());
-                            auto_save_player_registry(&registry);
+                            if persist_registry {
+                                auto_save_player_registry(&registry);
+                            }
+                            drop(registry);
+                            activate_character_profile(shared_settings, character_name, persist_settings);
                             return;
                         }
                     }
                 }
                 registry.add_party_member(character_name.clone());
-                auto_save_player_registry(&registry);
-                println!("Detected player from party join: {}", character_name);
+                if persist_registry {
+                    auto_save_player_registry(&registry);
+                }
+                eprintln!("Detected player from party join: {}", character_name);
             }
             return;
         }
@@ -142,7 +182,13 @@ pub fn process_parsed_line(
             if let Ok(mut tracker) = buff_tracker.lock() {
                 let buffs_before = tracker.active_buffs.len();
                 tracker.clear_all_buffs();
-                println!("Cleared {} buffs due to resting", buffs_before);
+                eprintln!("Cleared {} buffs due to resting", buffs_before);
+            }
+            // Everyone is back to full health after resting
+            health_deficits.clear();
+            recent_damage.clear();
+            if settings.rest_ends_encounter {
+                *encounter_closed = true;
             }
             return;
         }
@@ -150,7 +196,13 @@ pub fn process_parsed_line(
             // Remove specific buff when it expires
             if let Ok(mut tracker) = buff_tracker.lock() {
                 if tracker.remove_buff(spell_name) {
-                    println!("Removed expired buff: {}", spell_name);
+                    eprintln!("Removed expired buff: {}", spell_name);
+                }
+                // "wore off" lines are only logged for the main character's own effects
+                if let Ok(registry) = player_registry.lock()
+                    && let Some((_, main_character)) = registry.get_main_player_info()
+                {
+                    tracker.effects.remove(&main_character, spell_name);
                 }
             }
             return;
@@ -158,20 +210,42 @@ pub fn process_parsed_line(
         _ => {} // Continue processing other events
     }
 
-    // Only consider damage > 0 events for encounter timeout calculations
-    let should_update_combat_time = match &parsed {
+    // Lines that can start a new encounter after a lull
+    let starts_combat = match &parsed {
+        ParsedLine::Attack { .. } => true,
         ParsedLine::Damage { total, .. } => *total > 0,
+        ParsedLine::Initiative { .. } => settings.start_on_initiative,
         _ => false,
     };
-    
-    // Check if we need to start a new encounter (only based on damage > 0 events)
-    let should_start_new = if should_update_combat_time {
-        current_encounter.is_none() || 
-        (combat_time > *last_combat_time && 
-         combat_time.saturating_sub(*last_combat_time) > ENCOUNTER_TIMEOUT)
-    } else {
-        current_encounter.is_none()  // Only start new if no encounter exists
+    // Lines that show a fight is still going, so a short lull in damage doesn't split it
+    let continues_combat = starts_combat || matches!(parsed, ParsedLine::SpellResist { .. } | ParsedLine::Save { .. });
+
+    // No timeout until the current encounter has seen combat (last_combat_time is reset to 0 for a new one)
+    let timed_out = *last_combat_time > 0 &&
+        combat_time.saturating_sub(*last_combat_time) > settings.encounter_timeout;
+
+    let current_start = current_encounter
+        .and_then(|id| encounters.lock().unwrap().get(&id).map(|encounter| encounter.start_time));
+    let (split_due, held_together) = match (current_start, encounter_edits.lock()) {
+        (Some(start), Ok(edits)) => (edits.split_due(start, combat_time), edits.holds_together(start, combat_time)),
+        _ => (false, false),
     };
+
+    // Deaths are tracked by name, so only end the encounter once no enemy of a dead name is still fighting
+    if *close_pending && starts_combat {
+        *close_pending = false;
+        let revived = revived_combatants(&parsed, dead_combatants);
+        if revived.is_empty() {
+            *encounter_closed = true;
+        }
+        for name in revived {
+            dead_combatants.remove(&name);
+        }
+    }
+
+    // Manual splits and merges win over the automatic rules
+    let should_start_new = current_encounter.is_none() || split_due ||
+        (!held_together && starts_combat && (*encounter_closed || timed_out));
     
     if should_start_new {
         let new_id = {
@@ -182,7 +256,7 @@ pub fn process_parsed_line(
         };
         
         let new_encounter = Encounter::new(new_id, combat_time);
-        println!("Loading encounter #{} at timestamp {}", new_id, combat_time);
+        eprintln!("Loading encounter #{} at timestamp {}", new_id, combat_time);
         encounters.lock().unwrap().insert(new_id, new_encounter);
         *current_encounter = Some(new_id);
         spell_contexts.clear();
@@ -190,45 +264,111 @@ pub fn process_parsed_line(
         // pending_attacks.clear();
         pending_spells.clear();
         long_duration_spells.clear();
+        dead_combatants.clear();
+        *encounter_closed = false;
+        *close_pending = false;
+        *last_combat_time = 0;
     }
-    
-    // Only update last_combat_time for damage > 0 events (for timeout calculations)
-    if should_update_combat_time {
+
+    // A merged range stays one encounter even if everything in it died
+    if held_together && continues_combat {
+        *encounter_closed = false;
+        *close_pending = false;
+    }
+
+    if continues_combat && (starts_combat || !timed_out || held_together) && !*encounter_closed && !*close_pending {
+        // Lines before the first fighting (buffs, chat) don't count towards the encounter's length
+        if *last_combat_time == 0
+            && let Some(encounter_id) = *current_encounter
+            && let Some(encounter) = encounters.lock().unwrap().get_mut(&encounter_id)
+        {
+            encounter.start_time = combat_time;
+        }
         *last_combat_time = combat_time;
     }
     
     if let Some(encounter_id) = *current_encounter {
         let mut encounters_lock = encounters.lock().unwrap();
+        // A summon first shows up after its cast, so anything already fighting is someone else
+        let attacker_seen = match &parsed {
+            ParsedLine::Attack { attacker, .. } |
+            ParsedLine::Damage { attacker, .. } if !pending_summons.is_empty() => {
+                encounters_lock.values().any(|encounter| encounter.stats.contains_key(attacker))
+            }
+            _ => false,
+        };
         if let Some(encounter) = encounters_lock.get_mut(&encounter_id) {
-            encounter.end_time = combat_time;
+            // Stragglers after the last enemy died don't stretch the fight
+            if !*encounter_closed && !*close_pending {
+                encounter.end_time = combat_time;
+            }
             
             match parsed {
                 ParsedLine::Casting { .. } => {
                     // Ignore casting preparation - only track when spell is actually cast
                 }
                 ParsedLine::Casts { caster, spell, .. } => {
-                    // Check if this is a buff spell cast by the main player (only for real-time, not historical)
-                    if !is_historical {
-                        if let Ok(registry) = player_registry.lock() {
-                            // Check if caster is the main player character
-                            if let Some((_, main_character)) = registry.get_main_player_info() {
-                                if caster == main_character {
-                                    // Check if this spell is trackable (has a known duration)
-                                    if crate::models::BuffTracker::is_trackable_buff(&spell, settings) {
-                                        // Add buff to tracker
-                                        if let Ok(mut tracker) = buff_tracker.lock() {
-                                            println!("Adding buff: {} - Using settings: caster_level={}, cha_mod={}",
-                                                     spell, settings.caster_level, settings.charisma_modifier);
-                                            tracker.add_buff(spell.clone(), caster.clone(), settings);
-                                            println!("Tracking buff: {} cast by {}", spell, caster);
-                                        }
-                                    }
-                                }
-                            }
+                    let cast = SpellCast {
+                        caster: caster.clone(),
+                        spell: spell.clone(),
+                        timestamp: combat_time,
+                    };
+                    // Remember healing casts so the heal lines that follow can be attributed
+                    if is_healing_spell(&spell) {
+                        pending_heals.push(cast.clone());
+                    }
+                    // Remember all casts so saves and spell resists can be attributed
+                    recent_casts.retain(|cast| combat_time.saturating_sub(cast.timestamp) <= SPELL_CAST_WINDOW);
+                    if is_summon_spell(&spell) {
+                        pending_summons.retain(|cast| combat_time.saturating_sub(cast.timestamp) <= SUMMON_WINDOW);
+                        pending_summons.push(cast.clone());
+                    }
+                    recent_casts.push(cast);
+
+                    // Check if this is a buff spell cast by the main player. Historical lines are
+                    // tracked too: timers start at the cast's log time, so re-reading the log on
+                    // startup brings back the buffs that are still running.
+                    let is_main_character = player_registry.lock().is_ok_and(|registry| {
+                        registry.get_main_player_info().is_some_and(|(_, main_character)| caster == main_character)
+                    });
+                    if is_main_character
+                        && let Ok(mut tracker) = buff_tracker.lock()
+                        // Check if this spell is trackable (listed in buffs.json)
+                        && tracker.is_trackable_buff(&spell)
+                    {
+                        tracker.add_buff(spell.clone(), caster.clone(), combat_time, settings);
+                        if !is_historical {
+                            eprintln!("Tracking buff: {} cast by {} (caster_level={}, cha_mod={})",
+                                     spell, caster, settings.caster_level, settings.charisma_modifier);
                         }
                     }
+
+                    // Buffs cast by anyone in the party, for the effects panel
+                    if player_registry.lock().is_ok_and(|registry| registry.is_friendly(&caster))
+                        && let Ok(mut tracker) = buff_tracker.lock()
+                    {
+                        tracker.track_cast(&caster, &spell, combat_time, settings);
+                    }
                 }
-                ParsedLine::SpellResist { target, spell, .. } => {
+                ParsedLine::SpellResist { target, spell, result, roll, .. } => {
+                    let resisted = result == "SUCCESS";
+                    let known_caster = spell_contexts.iter()
+                        .find(|ctx| ctx.spell == spell)
+                        .map(|ctx| ctx.caster.clone());
+                    let caster = resolve_caster(Some(&spell), known_caster, &target, recent_casts, combat_time, SPELL_CAST_WINDOW);
+
+                    encounter.stats.entry(target.clone()).or_default()
+                        .saves_against.record_spell_resist(&spell, resisted, roll);
+                    if !resisted
+                        && let Some(caster) = &caster
+                    {
+                        track_debuff(buff_tracker, player_registry, caster, &target, &spell, combat_time, settings);
+                    }
+                    if let Some(caster) = caster {
+                        encounter.stats.entry(caster).or_default()
+                            .saves_forced.record_spell_resist(&spell, resisted, roll);
+                    }
+
                     // Clear existing pending spells since a new spell resist indicates previous spells didn't result in damage
                     pending_spells.clear();
                     
@@ -307,7 +447,42 @@ pub fn process_parsed_line(
                         }
                     }
                 }
-                ParsedLine::Save { target, .. } => {
+                ParsedLine::Save { target, save_type, element, result, roll, .. } => {
+                    let saved = result == "succeeded";
+                    let context = spell_contexts.iter()
+                        .find(|ctx| ctx.affected_targets.is_empty() || ctx.affected_targets.contains(&target))
+                        .map(|ctx| (ctx.spell.clone(), ctx.caster.clone()));
+                    let caster = match &context {
+                        Some((spell, known_caster)) => resolve_caster(Some(spell), Some(known_caster.clone()), &target, recent_casts, combat_time, SPELL_CAST_WINDOW),
+                        None => resolve_caster(None, None, &target, recent_casts, combat_time, SPELL_CAST_WINDOW),
+                    };
+
+                    // The spell the save was against: the current spell context, else the latest cast by someone else
+                    let spell = context.map(|(spell, _)| spell).or_else(|| {
+                        recent_casts.iter().rev()
+                            .filter(|cast| combat_time.saturating_sub(cast.timestamp) <= SPELL_CAST_WINDOW)
+                            .find(|cast| cast.caster != target)
+                            .map(|cast| cast.spell.clone())
+                    });
+                    if let (Some(caster), Some(spell)) = (&caster, &spell) {
+                        if saved {
+                            // A failed spell resistance check may already have put it on the target
+                            if let Ok(mut tracker) = buff_tracker.lock() {
+                                let since = tracker.timer_start(combat_time.saturating_sub(SPELL_CAST_WINDOW));
+                                tracker.effects.remove_recent(&target, spell, since);
+                            }
+                        } else {
+                            track_debuff(buff_tracker, player_registry, caster, &target, spell, combat_time, settings);
+                        }
+                    }
+
+                    encounter.stats.entry(target.clone()).or_default()
+                        .saves_against.record_save(&save_type, &element, caster.as_deref(), saved, roll);
+                    if let Some(caster) = caster {
+                        encounter.stats.entry(caster).or_default()
+                            .saves_forced.record_save(&save_type, &element, Some(&target), saved, roll);
+                    }
+
                     // For saves, match with the most recent spell context and mark pending spells
                     for ctx in spell_contexts.iter_mut() {
                         if ctx.affected_targets.is_empty() || ctx.affected_targets.contains(&target) {
@@ -330,7 +505,7 @@ pub fn process_parsed_line(
                         }
                     }
                 }
-                ParsedLine::Attack { attacker, target, result, concealment, timestamp } => {
+                ParsedLine::Attack { attacker, target, result, concealment, roll, modifiers, timestamp } => {
                     // Clear pending spells when an attack roll happens
                     pending_spells.clear();
 
@@ -338,9 +513,20 @@ pub fn process_parsed_line(
                     pending_attacks.retain(|attack| {
                         combat_time.saturating_sub(attack.timestamp) <= 3
                     });
+
+                    // Summons' and pets' swings count for their owner, like their damage
+                    let (actual_attacker, _) = match player_registry.lock() {
+                        Ok(mut registry) => {
+                            pending_summons.retain(|cast| combat_time.saturating_sub(cast.timestamp) <= SUMMON_WINDOW);
+                            learn_summon(&mut registry, pending_summons, settings, attacker_seen, &attacker, &target);
+                            split_pet_name(&attacker, &registry, settings.merge_pet_damage)
+                        }
+                        Err(_) => split_summon_name(&attacker),
+                    };
                     
-                    let attacker_stats = encounter.stats.entry(attacker.clone()).or_default();
+                    let attacker_stats = encounter.stats.entry(actual_attacker).or_default();
                     attacker_stats.update_action_time(timestamp);
+                    let is_hit = result == "hit" || result == "critical hit";
                     match result.as_str() {
                         "hit" => {
                             attacker_stats.hits += 1;
@@ -349,11 +535,12 @@ pub fn process_parsed_line(
                                 target: target.clone(),
                                 timestamp: combat_time,
                                 is_crit: false,
+                                modifiers,
                             });
                         }
                         "miss" => {
                             attacker_stats.misses += 1;
-                            if concealment {
+                            if concealment.is_some() {
                                 attacker_stats.concealment_dodges += 1;
                             }
                         }
@@ -364,10 +551,42 @@ pub fn process_parsed_line(
                                 target: target.clone(),
                                 timestamp: combat_time,
                                 is_crit: true,
+                                modifiers,
                             });
                         }
                         _ => {}
                     }
+
+                    if is_hit {
+                        if modifiers.sneak_attack {
+                            attacker_stats.sneak_attacks += 1;
+                        }
+                        if modifiers.death_attack {
+                            attacker_stats.death_attacks += 1;
+                        }
+                        if modifiers.off_hand {
+                            attacker_stats.off_hand_hits += 1;
+                        }
+                    }
+
+                    if let Some(roll) = roll {
+                        attacker_stats.attack_rolls += 1;
+                        attacker_stats.attack_bonus_sum += roll.bonus as i64;
+                        attacker_stats.max_attack_bonus = Some(attacker_stats.max_attack_bonus.map_or(roll.bonus, |max| max.max(roll.bonus)));
+                        match roll.d20 {
+                            1 => attacker_stats.natural_ones += 1,
+                            20 => attacker_stats.natural_twenties += 1,
+                            _ => {
+                                // Natural 1s and 20s ignore AC, and a concealed miss may have beaten it
+                                let bounds = attacker_stats.ac_by_target.entry(target.clone()).or_default();
+                                if is_hit {
+                                    bounds.record_hit(roll.total);
+                                } else if concealment.is_none() {
+                                    bounds.record_miss(roll.total);
+                                }
+                            }
+                        }
+                    }
                     // Only create stats entries when damage is dealt, not just on attacks
                 }
                 ParsedLine::Damage { attacker, target, total, breakdown, timestamp } => {
@@ -394,7 +613,7 @@ pub fn process_parsed_line(
                         // Check if damage type matches the spell's expected damage type EXCLUSIVELY
                         if let Some(expected_type) = get_spell_damage_type(&spell.spell) {
                             // For specific damage type spells, only match if the damage contains ONLY that type
-                            breakdown.len() == 1 && breakdown.contains_key(expected_type)
+                            breakdown.len() == 1 && breakdown.contains_key(&expected_type)
                         } else {
                             // For unspecified damage types, match any damage
                             true
@@ -402,7 +621,7 @@ pub fn process_parsed_line(
                     });
                     
                     // If we found a matching long-duration spell, use it and don't interfere with other tracking
-                    let (damage_source, is_from_crit, is_weapon_buff_damage) = if let Some(long_spell) = matching_long_duration_spell {
+                    let (damage_source, is_from_crit, is_weapon_buff_damage, attack_modifiers) = if let Some(long_spell) = matching_long_duration_spell {
                         let spell_name = long_spell.spell.clone();
                         let caster_was_unknown = long_spell.caster == "Unknown Caster";
                         
@@ -423,7 +642,7 @@ pub fn process_parsed_line(
                             }
                         }
                         
-                        (format!("Spell: {}", spell_name), false, false)
+                        (format!("Spell: {}", spell_name), false, false, AttackModifiers::default())
                     } else {
                         // STEP 2: No long-duration spell matched, use normal attack/spell logic
                         
@@ -452,11 +671,11 @@ pub fn process_parsed_line(
                         };
                         
                         // Check if this damage is exclusively Fire (weapon buff)
-                        let is_weapon_buff = breakdown.len() == 1 && breakdown.contains_key("Fire");
+                        let is_weapon_buff = breakdown.len() == 1 && breakdown.contains_key(&DamageType::Fire);
                         
                         if is_weapon_buff && !pending_attacks.is_empty() && pending_spells.is_empty() {
                             // This is weapon buff damage, count as Attack but don't consume the attack
-                            ("Attack".to_string(), false, true)
+                            ("Attack".to_string(), false, true, AttackModifiers::default())
                         } else {
                             match (oldest_spell, oldest_attack) {
                                 (Some((spell_idx, spell)), Some((attack_idx, attack_timestamp))) => {
@@ -464,7 +683,7 @@ pub fn process_parsed_line(
                                     // Only classify as Attack if damage includes Physical
                                     let should_use_spell = spell.had_save_roll || spell.had_damage_immunity
                                         || spell.timestamp <= attack_timestamp
-                                        || !breakdown.contains_key("Physical"); // No Physical = not an attack
+                                        || !breakdown.contains_key(&DamageType::Physical); // No Physical = not an attack
 
                                     if should_use_spell {
                                         let pending_spell = pending_spells.remove(spell_idx);
@@ -478,10 +697,10 @@ pub fn process_parsed_line(
                                                 }
                                             }
                                         }
-                                        (format!("Spell: {}", pending_spell.spell), false, false)
+                                        (format!("Spell: {}", pending_spell.spell), false, false, AttackModifiers::default())
                                     } else {
                                         let attack = pending_attacks.remove(attack_idx);
-                                        ("Attack".to_string(), attack.is_crit, false)
+                                        ("Attack".to_string(), attack.is_crit, false, attack.modifiers)
                                     }
                                 },
                                 (Some((spell_idx, _)), None) => {
@@ -496,35 +715,35 @@ pub fn process_parsed_line(
                                             }
                                         }
                                     }
-                                    (format!("Spell: {}", pending_spell.spell), false, false)
+                                    (format!("Spell: {}", pending_spell.spell), false, false, AttackModifiers::default())
                                 },
                                 (None, Some((attack_idx, _))) => {
                                     // Only attack found - but only classify as Attack if damage includes Physical
-                                    if breakdown.contains_key("Physical") {
+                                    if breakdown.contains_key(&DamageType::Physical) {
                                         let attack = pending_attacks.remove(attack_idx);
-                                        ("Attack".to_string(), attack.is_crit, false)
+                                        ("Attack".to_string(), attack.is_crit, false, attack.modifiers)
                                     } else {
                                         // No Physical damage, classify as Unknown (don't consume attack)
-                                        ("Unknown".to_string(), false, false)
+                                        ("Unknown".to_string(), false, false, AttackModifiers::default())
                                     }
                                 },
                                 (None, None) => {
                                     // Neither found
-                                    ("Unknown".to_string(), false, false)
+                                    ("Unknown".to_string(), false, false, AttackModifiers::default())
                                 }
                             }
                         }
                     };
                     
-                    // Handle summon damage attribution - check if attacker contains " | " (summon pattern)
-                    let (actual_attacker, summon_name) = if let Some(pipe_pos) = attacker.find(" | ") {
-                        // This is a summon attack - attribute damage to the player before the pipe
-                        let player_name = attacker[..pipe_pos].trim().to_string();
-                        let summon_name = attacker[pipe_pos + 3..].trim().to_string();
-                        (player_name, Some(summon_name))
-                    } else {
-                        // Regular attacker
-                        (attacker.clone(), None)
+                    // Handle summon damage attribution - attribute damage to the player before the pipe
+                    // (or to the owner of a known pet, if pet damage is merged)
+                    let (actual_attacker, summon_name) = match player_registry.lock() {
+                        Ok(mut registry) => {
+                            pending_summons.retain(|cast| combat_time.saturating_sub(cast.timestamp) <= SUMMON_WINDOW);
+                            learn_summon(&mut registry, pending_summons, settings, attacker_seen, &attacker, &target);
+                            split_pet_name(&attacker, &registry, settings.merge_pet_damage)
+                        }
+                        Err(_) => split_summon_name(&attacker),
                     };
 
                     // Modify damage source to include summon information
@@ -543,6 +762,7 @@ pub fn process_parsed_line(
                         let attacker_stats = encounter.stats.entry(actual_attacker.clone()).or_default();
                         attacker_stats.update_action_time(timestamp);
                         attacker_stats.total_damage_dealt += total;
+                        attacker_stats.timeline.record_dealt(timestamp, total);
 
                         *attacker_stats.damage_by_source_dealt.entry(final_damage_source.clone()).or_default() += total;
                         
@@ -565,6 +785,16 @@ pub fn process_parsed_line(
                                 .or_default() += amount;
                         }
                         
+                        if attack_modifiers.sneak_attack {
+                            attacker_stats.sneak_attack_damage += total;
+                        }
+                        if attack_modifiers.death_attack {
+                            attacker_stats.death_attack_damage += total;
+                        }
+                        if attack_modifiers.off_hand {
+                            attacker_stats.off_hand_damage += total;
+                        }
+
                         // Track hit vs crit vs weapon buff damage separately for attacks
                         if damage_source == "Attack" {
                             if is_weapon_buff_damage {
@@ -615,11 +845,33 @@ pub fn process_parsed_line(
                         }
                     }
                     
+                    *health_deficits.entry(target.clone()).or_default() += total;
+
+                    // Anyone fighting after their death was a different creature with the same name
+                    dead_combatants.remove(&actual_attacker);
+                    if dead_combatants.get(&target).is_some_and(|&died_at| timestamp > died_at) {
+                        dead_combatants.remove(&target);
+                    }
+
+                    // Remember the last few hits on the target for its death recap
+                    let recent = recent_damage.entry(target.clone()).or_default();
+                    recent.push(DamageEvent {
+                        timestamp,
+                        attacker: actual_attacker.clone(),
+                        source: final_damage_source.clone(),
+                        amount: total,
+                        damage_types: breakdown.clone(),
+                    });
+                    if recent.len() > DEATH_RECAP_EVENTS {
+                        recent.remove(0);
+                    }
+
                     // Handle target stats
                     {
                         let target_stats = encounter.stats.entry(target.clone()).or_default();
                         target_stats.update_action_time(timestamp);
                         target_stats.total_damage_received += total;
+                        target_stats.timeline.record_taken(timestamp, total);
                         
                         // Track damage source for received damage (use final_damage_source which includes summon info if applicable)
                         let received_source = format!("{} ({})", actual_attacker, final_damage_source);
@@ -668,7 +920,7 @@ pub fn process_parsed_line(
                     let target_stats = encounter.stats.entry(target.clone()).or_default();
                     target_stats.update_action_time(timestamp);
                     target_stats.total_damage_absorbed += amount;
-                    *target_stats.absorbed_by_type.entry("Resistance".to_string()).or_default() += amount;
+                    target_stats.absorbed_by_resistance += amount;
 
                     // Mark any pending spells for this target as having damage immunity absorption
                     for pending_spell in pending_spells.iter_mut() {
@@ -688,7 +940,7 @@ pub fn process_parsed_line(
                     let target_stats = encounter.stats.entry(target.clone()).or_default();
                     target_stats.update_action_time(timestamp);
                     target_stats.total_damage_absorbed += amount;
-                    *target_stats.absorbed_by_type.entry("Reduction".to_string()).or_default() += amount;
+                    target_stats.absorbed_by_reduction += amount;
 
                     // Mark any pending spells for this target as having damage immunity absorption
                     for pending_spell in pending_spells.iter_mut() {
@@ -704,6 +956,76 @@ pub fn process_parsed_line(
                         }
                     }
                 }
+                ParsedLine::Heal { healer, target, amount, timestamp } => {
+                    pending_heals.retain(|heal| {
+                        combat_time.saturating_sub(heal.timestamp) <= HEAL_CAST_WINDOW
+                    });
+
+                    // Most recent healing cast by this healer (or by anyone, if the line doesn't say who healed)
+                    // Casts are not consumed since one cast can heal several targets (Mass Heal, Healing Circle)
+                    let cast = pending_heals.iter().rev().find(|heal| {
+                        healer.as_ref().is_none_or(|name| heal.caster == *name)
+                    });
+                    let healer = healer.or_else(|| cast.map(|heal| heal.caster.clone()));
+                    let source = cast
+                        .map(|heal| format!("Spell: {}", heal.spell))
+                        .unwrap_or_else(|| "Unknown".to_string());
+
+                    // Anything above the damage taken since the last rest/heal is counted as overheal
+                    let deficit = health_deficits.entry(target.clone()).or_default();
+                    let effective = amount.min(*deficit);
+                    let overheal = amount - effective;
+                    *deficit -= effective;
+
+                    {
+                        let target_stats = encounter.stats.entry(target.clone()).or_default();
+                        target_stats.healing_received += amount;
+                        target_stats.overhealing_received += overheal;
+                        let healer_name = healer.clone().unwrap_or_else(|| "Unknown".to_string());
+                        *target_stats.healing_by_healer_received.entry(healer_name).or_default() += amount;
+                    }
+
+                    if let Some(healer) = healer {
+                        let healer_stats = encounter.stats.entry(healer).or_default();
+                        healer_stats.update_action_time(timestamp);
+                        healer_stats.healing_done += amount;
+                        healer_stats.effective_healing_done += effective;
+                        healer_stats.overhealing_done += overheal;
+                        *healer_stats.healing_by_target_done.entry(target.clone()).or_default() += amount;
+                        *healer_stats.healing_by_source_done.entry(source).or_default() += amount;
+                    }
+                }
+                ParsedLine::Death { killer, victim, timestamp } => {
+                    let (killer, _) = match player_registry.lock() {
+                        Ok(registry) => split_pet_name(&killer, &registry, settings.merge_pet_damage),
+                        Err(_) => split_summon_name(&killer),
+                    };
+                    encounter.stats.entry(killer.clone()).or_default().kills += 1;
+                    encounter.stats.entry(victim.clone()).or_default().deaths += 1;
+
+                    let recap = recent_damage.remove(&victim).unwrap_or_default();
+                    health_deficits.remove(&victim);
+                    if let Ok(mut tracker) = buff_tracker.lock() {
+                        tracker.effects.remove_target(&victim);
+                    }
+                    encounter.deaths.push(DeathRecord::new(victim.clone(), killer, timestamp, recap));
+                    dead_combatants.insert(victim.clone(), timestamp);
+
+                    // Once every enemy in the fight (or the main one, if so configured) is dead
+                    // the encounter is over, even before the timeout, unless the next fighting
+                    // shows another enemy by the same name
+                    if let Ok(registry) = player_registry.lock()
+                        && !registry.is_friendly(&victim)
+                        && (all_enemies_dead(encounter, dead_combatants, &registry)
+                            || (settings.end_on_primary_target_death
+                                && primary_target(encounter, &registry).as_deref() == Some(victim.as_str())))
+                    {
+                        *close_pending = true;
+                    }
+                }
+                ParsedLine::Initiative { .. } => {
+                    // Only used to find where encounters start
+                }
                 // Player identification and rest events are handled at the top of the function
                 ParsedLine::PlayerJoin { .. } |
                 ParsedLine::PlayerChat { .. } |
@@ -716,4 +1038,113 @@ pub fn process_parsed_line(
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Split a "Player | Summon" name into the player and the summon
+fn split_summon_name(name: &str) -> (String, Option<String>) {
+    match name.find(" | ") {
+        Some(pipe_pos) => (name[..pipe_pos].trim().to_string(), Some(name[pipe_pos + 3..].trim().to_string())),
+        None => (name.to_string(), None),
+    }
+}
+
+/// Like `split_summon_name`, but other known pets are split off their owner too when `merge_pets` is set
+fn split_pet_name(name: &str, registry: &PlayerRegistry, merge_pets: bool) -> (String, Option<String>) {
+    let (owner, summon) = split_summon_name(name);
+    if summon.is_none()
+        && merge_pets
+        && let Some(owner) = registry.pet_owner(name)
+    {
+        return (owner, Some(name.to_string()));
+    }
+    (owner, summon)
+}
+
+/// A creature nobody knows that starts fighting our enemies soon after a summon spell
+/// is taken to be the summoner's pet, if this is the first it has been seen in any encounter
+fn learn_summon(registry: &mut PlayerRegistry, pending_summons: &mut Vec<SpellCast>, settings: &AppSettings, attacker_seen: bool, attacker: &str, target: &str) {
+    registry.pet_name_rules.update(&settings.pet_name_rules);
+    if pending_summons.is_empty() || attacker_seen || registry.is_friendly(attacker) || registry.is_friendly(target) {
+        return;
+    }
+    let Some(index) = pending_summons.iter().position(|cast| cast.caster != target && registry.is_friendly(&cast.caster)) else {
+        return;
+    };
+    let summon = pending_summons.remove(index);
+    eprintln!("Learned pet '{}' of '{}' (from {})", attacker, summon.caster, summon.spell);
+    registry.add_pet(attacker.to_string(), summon.caster);
+}
+
+/// Swap in the buff-duration profile saved for the main player's current character
+fn activate_character_profile(settings: &Mutex<AppSettings>, character: &str, persist: bool) {
+    if let Ok(mut settings) = settings.lock()
+        && settings.switch_character(character)
+    {
+        eprintln!("Using character profile '{}': caster_level={}, cha_mod={}, modifiers={:?}",
+                 character, settings.caster_level, settings.charisma_modifier, settings.buff_modifiers);
+        if persist {
+            auto_save_app_settings(&settings);
+        }
+    }
+}
+
+/// Put a debuff that got through on its target, if a party member cast it
+fn track_debuff(buff_tracker: &Mutex<BuffTracker>, player_registry: &Mutex<PlayerRegistry>, caster: &str, target: &str, spell: &str, log_time: u64, settings: &AppSettings) {
+    let friendly_caster = player_registry.lock().is_ok_and(|registry| registry.is_friendly(caster) && !registry.is_friendly(target));
+    if friendly_caster
+        && let Ok(mut tracker) = buff_tracker.lock()
+    {
+        tracker.track_debuff(caster, target, spell, log_time, settings);
+    }
+}
+
+/// Combatants marked dead that a line shows are still around: attacking, or hit after they died
+fn revived_combatants(parsed: &ParsedLine, dead_combatants: &HashMap<String, u64>) -> Vec<String> {
+    let (attacker, target, timestamp) = match parsed {
+        ParsedLine::Attack { attacker, target, timestamp, .. } |
+        ParsedLine::Damage { attacker, target, timestamp, .. } => (attacker, target, *timestamp),
+        _ => return Vec::new(),
+    };
+    let mut revived = Vec::new();
+    if dead_combatants.contains_key(attacker) {
+        revived.push(attacker.clone());
+    }
+    if dead_combatants.get(target).is_some_and(|&died_at| timestamp > died_at) {
+        revived.push(target.clone());
+    }
+    revived
+}
+
+/// Whether every non-player that dealt or took damage in the encounter is dead
+fn all_enemies_dead(encounter: &Encounter, dead_combatants: &HashMap<String, u64>, registry: &PlayerRegistry) -> bool {
+    let mut enemies = encounter.stats.iter()
+        .filter(|(name, stats)| {
+            !registry.is_friendly(name) && (stats.total_damage_dealt > 0 || stats.total_damage_received > 0)
+        })
+        .peekable();
+    enemies.peek().is_some() && enemies.all(|(name, _)| dead_combatants.contains_key(name))
+}
+
+/// The enemy that took the most damage in the encounter
+fn primary_target(encounter: &Encounter, registry: &PlayerRegistry) -> Option<String> {
+    encounter.stats.iter()
+        .filter(|(name, stats)| !registry.is_friendly(name) && stats.total_damage_received > 0)
+        .max_by_key(|(_, stats)| stats.total_damage_received)
+        .map(|(name, _)| name.clone())
+}
+
+/// Work out who cast the spell behind a save or spell resist line: the caster already
+/// learned from damage if there is one, else the latest cast of that spell (or of any
+/// spell, by someone other than the target, when the line doesn't name one)
+fn resolve_caster(spell: Option<&str>, known_caster: Option<String>, target: &str, recent_casts: &[SpellCast], now: u64, window: u64) -> Option<String> {
+    if let Some(caster) = known_caster.filter(|caster| caster != "Unknown Caster") {
+        return Some(caster);
+    }
+    recent_casts.iter().rev()
+        .filter(|cast| now.saturating_sub(cast.timestamp) <= window)
+        .find(|cast| match spell {
+            Some(spell) => cast.spell == spell,
+            None => cast.caster != target,
+        })
+        .map(|cast| cast.caster.clone())
+}
@@ -2,15 +2,15 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 lazy_static! {
-    pub static ref RE_ATTACK: Regex = Regex::new(r"^(?:[^:]+: )*(?P<attacker>.+?) attacks (?P<target>.+?) : (?:\*target concealed: (?P<concealment>\d+)%\* : )?\*(?P<result>hit|miss|critical hit)\*").unwrap();
-    pub static ref RE_CONCEALMENT: Regex = Regex::new(r"^(?:[^:]+: )*(?P<attacker>.+?) attacks (?P<target>.+?) : \*target concealed: (?P<concealment>\d+)%\* : \(.+\)").unwrap();
+    pub static ref RE_ATTACK: Regex = Regex::new(r"^(?P<prefixes>(?:[^:]+: )*)(?P<attacker>.+?) attacks (?P<target>.+?) : (?:\*target concealed: (?P<concealment>\d+)%\* : )?\*(?P<result>hit|miss|critical hit)\*(?: : \((?P<d20>\d+) \+ (?P<bonus>-?\d+) = (?P<total>-?\d+))?").unwrap();
+    pub static ref RE_CONCEALMENT: Regex = Regex::new(r"^(?P<prefixes>(?:[^:]+: )*)(?P<attacker>.+?) attacks (?P<target>.+?) : \*target concealed: (?P<concealment>\d+)%\* : \(.+\)").unwrap();
     pub static ref RE_DAMAGE: Regex = Regex::new(r"^(?P<attacker>.+?) damages (?P<target>.+?): (?P<total>\d+) \((?P<breakdown>.+)\)").unwrap();
-    pub static ref RE_ABSORB: Regex = Regex::new(r"^(?P<target>.+?) : Damage Immunity absorbs (?P<amount>\d+) point\(s\) of (?P<type>\w+)").unwrap();
+    pub static ref RE_ABSORB: Regex = Regex::new(r"^(?P<target>.+?) : Damage Immunity absorbs (?P<amount>\d+) point\(s\) of (?P<type>\w+(?: \w+)*)").unwrap();
     pub static ref RE_ABSORB_RESISTANCE: Regex = Regex::new(r"^(?P<target>.+?) : Damage Resistance absorbs (?P<amount>\d+) damage").unwrap();
     pub static ref RE_ABSORB_REDUCTION: Regex = Regex::new(r"^(?P<target>.+?) : Damage Reduction absorbs (?P<amount>\d+) damage").unwrap();
     pub static ref RE_TIMESTAMP: Regex = Regex::new(r"^\[CHAT WINDOW TEXT\] \[([^\]]+)\]").unwrap();
-    pub static ref RE_SPELL_RESIST: Regex = Regex::new(r"^SPELL RESIST: (?P<target>.+?) attempts to resist: (?P<spell>.+?) - Result:\s+(?P<result>FAILED|SUCCESS)").unwrap();
-    pub static ref RE_SAVE: Regex = Regex::new(r"^SAVE: (?P<target>.+?) : (?P<save_type>.+?) vs\. (?P<element>.+?) : \*(?P<result>failed|succeeded)\*").unwrap();
+    pub static ref RE_SPELL_RESIST: Regex = Regex::new(r"^SPELL RESIST: (?P<target>.+?) attempts to resist: (?P<spell>.+?) - Result:\s+(?P<result>FAILED|SUCCESS)(?: : \((?P<d20>\d+) \+ (?P<bonus>-?\d+) = (?P<total>-?\d+) vs\. SR: (?P<dc>-?\d+)\))?").unwrap();
+    pub static ref RE_SAVE: Regex = Regex::new(r"^SAVE: (?P<target>.+?) : (?P<save_type>.+?) vs\. (?P<element>.+?) : \*(?P<result>failed|succeeded)\*(?: : \((?P<d20>\d+) \+ (?P<bonus>-?\d+) = (?P<total>-?\d+) vs\. DC: (?P<dc>-?\d+)\))?").unwrap();
     pub static ref RE_CASTING: Regex = Regex::new(r"^(?P<caster>.+?) casting (?P<spell>.+)").unwrap();
     pub static ref RE_CASTS: Regex = Regex::new(r"^(?P<caster>.+?) casts (?P<spell>.+)").unwrap();
 
@@ -29,6 +29,11 @@ lazy_static! {
     // Initiative roll detection regex
     pub static ref RE_INITIATIVE: Regex = Regex::new(r"^(?P<character>.+?) : Initiative Roll :").unwrap();
 
+    // Death detection regex: "Thorin killed Orc Warrior" (no ':' so chat lines can't match)
+    pub static ref RE_KILLED: Regex = Regex::new(r"^(?P<killer>[^:\[]+?) killed (?P<victim>[^:\[]+?)\.?$").unwrap();
+
     // Healing detection regex
     pub static ref RE_HEALED: Regex = Regex::new(r"^(?P<character>.+?) : Healed (?P<amount>\d+) hit points?\.").unwrap();
+    // Heals are also logged as negative damage: "Aria damages Elara: -30 (30 Healing)"
+    pub static ref RE_HEAL_DAMAGE: Regex = Regex::new(r"^(?P<healer>.+?) damages (?P<target>.+?): -(?P<amount>\d+) \(").unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use crate::models::{Encounter, EncounterEdits, EncounterHistory, HistoryEntry, SpellContext, PendingAttack, PendingSpell, LongDurationSpell, SpellCast, DamageEvent, PlayerRegistry, BuffTracker, AppSettings, AlertEvent, AlertQueue};
+use crate::parsing::{ParsedLine, parse_log_line, process_parsed_line};
+use crate::utils::{LogClock, auto_save_history_entry};
+
+/// Per-line parser state that only matters while an encounter is being built
+#[derive(Debug, Clone, Default)]
+pub struct CombatState {
+    pub last_combat_time: u64,
+    pub current_encounter: Option<u64>,
+    pub spell_contexts: Vec<SpellContext>,
+    pub pending_attacks: Vec<PendingAttack>,
+    pub pending_spells: Vec<PendingSpell>,
+    pub long_duration_spells: Vec<LongDurationSpell>,
+    pub pending_heals: Vec<SpellCast>,
+    /// Recent casts of any spell, to attribute saves and spell resistance to a caster
+    pub recent_casts: Vec<SpellCast>,
+    /// Summon spells whose creature hasn't shown up fighting yet
+    pub pending_summons: Vec<SpellCast>,
+    /// Damage taken since the last rest or heal, per combatant, for overheal estimates
+    pub health_deficits: HashMap<String, u32>,
+    /// Last few damage events taken per combatant, for death recaps
+    pub recent_damage: HashMap<String, Vec<DamageEvent>>,
+    /// Combatants that died in the current encounter and when, until they show up fighting again
+    pub dead_combatants: HashMap<String, u64>,
+    /// Set once the current encounter is over (enemies dead, rest); the next hit starts a new one
+    pub encounter_closed: bool,
+    /// Every enemy name in the encounter has died, but a pack can share one name: the next fighting line
+    /// closes the encounter unless an enemy of a dead name shows up in it
+    pub close_pending: bool,
+}
+
+/// Point-in-time copy of everything a session has built so far
+#[derive(Debug, Clone, Default)]
+pub struct SessionSnapshot {
+    pub encounters: HashMap<u64, Encounter>,
+    pub current_encounter_id: Option<u64>,
+}
+
+/// Owns the combat engine state for one log stream.
+///
+/// Shared pieces live behind `Arc<Mutex<..>>` so a GUI (or any other consumer)
+/// can hold clones of them while the session is fed from another thread.
+pub struct CombatSession {
+    pub encounters: Arc<Mutex<HashMap<u64, Encounter>>>,
+    pub current_encounter_id: Arc<Mutex<Option<u64>>>,
+    pub encounter_counter: Arc<Mutex<u64>>,
+    pub player_registry: Arc<Mutex<PlayerRegistry>>,
+    pub buff_tracker: Arc<Mutex<BuffTracker>>,
+    pub settings: Arc<Mutex<AppSettings>>,
+    /// Manual encounter splits and merges, kept across `reset` so a reparse applies them
+    pub encounter_edits: Arc<Mutex<EncounterEdits>>,
+    /// Write players.json whenever the registry changes (off for headless use)
+    pub persist_player_registry: bool,
+    /// Encounters from earlier sessions; finished encounters are added as they end
+    pub history: Arc<Mutex<EncounterHistory>>,
+    /// Append finished encounters to encounters.jsonl (off for headless use)
+    pub persist_history: bool,
+    /// Write settings.json when a character switch swaps in another profile (off for headless use)
+    pub persist_settings: bool,
+    /// Alerts raised by live lines, played by the GUI
+    pub alerts: Arc<Mutex<AlertQueue>>,
+    pub(crate) state: CombatState,
+    /// Dates log lines, which carry no year
+    clock: LogClock,
+    /// Spell and timestamp of the previous line if it was a cast, used to skip recast "wore off" lines
+    previous_cast: Option<(String, u64)>,
+}
+
+impl Default for CombatSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CombatSession {
+    pub fn new() -> Self {
+        Self {
+            encounters: Arc::new(Mutex::new(HashMap::new())),
+            current_encounter_id: Arc::new(Mutex::new(None)),
+            encounter_counter: Arc::new(Mutex::new(1)),
+            player_registry: Arc::new(Mutex::new(PlayerRegistry::new())),
+            buff_tracker: Arc::new(Mutex::new(BuffTracker::new())),
+            settings: Arc::new(Mutex::new(AppSettings::default())),
+            encounter_edits: Arc::new(Mutex::new(EncounterEdits::default())),
+            persist_player_registry: false,
+            history: Arc::new(Mutex::new(EncounterHistory::default())),
+            persist_history: false,
+            persist_settings: false,
+            alerts: Arc::new(Mutex::new(AlertQueue::default())),
+            state: CombatState::default(),
+            clock: LogClock::default(),
+            previous_cast: None,
+        }
+    }
+
+    /// Date the lines that follow using this log file's modification time for the year.
+    /// When several logs are read one after another, the fight in progress ends with its file.
+    pub fn set_log_file(&mut self, path: &Path) {
+        if let Some(current) = self.state.current_encounter {
+            self.archive_encounter(current);
+            self.state.encounter_closed = true;
+        }
+        self.clock = LogClock::for_file(path);
+        if let Ok(mut history) = self.history.lock() {
+            history.active_log = path.file_name().map(|name| name.to_string_lossy().into_owned());
+        }
+    }
+
+    /// Feed one live log line. Returns true if the line was understood by the parser.
+    pub fn feed_line(&mut self, line: &str) -> bool {
+        self.feed(line, false)
+    }
+
+    /// Feed one line of an already-written log (buff timers start at the lines' log times)
+    pub fn feed_historical_line(&mut self, line: &str) -> bool {
+        self.feed(line, true)
+    }
+
+    fn feed(&mut self, line: &str, is_historical: bool) -> bool {
+        let Some(parsed) = parse_log_line(line, &mut self.clock) else {
+            self.previous_cast = None;
+            return false;
+        };
+
+        let is_recast_expiry = match (&parsed, &self.previous_cast) {
+            (ParsedLine::BuffExpired { spell_name, timestamp }, Some((spell, cast_time))) => {
+                spell == spell_name && cast_time == timestamp
+            }
+            _ => false,
+        };
+        self.previous_cast = match &parsed {
+            ParsedLine::Casts { spell, timestamp, .. } => Some((spell.clone(), *timestamp)),
+            _ => None,
+        };
+        if is_recast_expiry {
+            // The buff was recast on the same tick, so the "wore off" line is stale
+            return true;
+        }
+
+        let combat_time = parsed.timestamp();
+        if !is_historical {
+            if let Ok(mut tracker) = self.buff_tracker.lock() {
+                tracker.sync_log_time(combat_time);
+            }
+            self.raise_alerts(&parsed);
+        }
+        let previous_encounter = self.state.current_encounter;
+        process_parsed_line(self, parsed, is_historical);
+
+        if let Some(previous) = previous_encounter
+            && self.state.current_encounter != previous_encounter
+        {
+            self.archive_encounter(previous);
+        }
+
+        if !is_historical {
+            let current_encounter = self.state.current_encounter;
+
+            // Update the shared current_encounter_id when it changes
+            if let Ok(mut shared_current) = self.current_encounter_id.lock()
+                && *shared_current != current_encounter
+            {
+                *shared_current = current_encounter;
+                if let Some(encounter_id) = current_encounter {
+                    eprintln!("Started new encounter #{} at timestamp {}", encounter_id, combat_time);
+                }
+            }
+
+            // Update most damaged for the current encounter
+            if let Some(encounter_id) = current_encounter
+                && let Ok(mut encounters_lock) = self.encounters.lock()
+                && let Some(encounter) = encounters_lock.get_mut(&encounter_id)
+            {
+                encounter.update_most_damaged();
+            }
+        }
+        true
+    }
+
+    /// Check a live line against the user's alert rules
+    fn raise_alerts(&self, parsed: &ParsedLine) {
+        let rules = match self.settings.lock() {
+            Ok(settings) if !settings.alert_rules.is_empty() => settings.alert_rules.clone(),
+            _ => return,
+        };
+        // Taken before locking the registry, which is locked after the encounters elsewhere
+        let caster_opponents = match parsed {
+            ParsedLine::Casts { caster, .. } => self.current_opponents(caster),
+            _ => Vec::new(),
+        };
+        let event = {
+            let Ok(registry) = self.player_registry.lock() else {
+                return;
+            };
+            match parsed {
+                // "wore off" lines are only logged for the main character's own effects
+                ParsedLine::BuffExpired { spell_name, .. } => AlertEvent::BuffWoreOff { buff: spell_name.clone() },
+                ParsedLine::Damage { attacker, target, total, .. }
+                    if registry.get_main_player_info().is_some_and(|(_, main_character)| *target == main_character) =>
+                {
+                    AlertEvent::HitTaken { damage: *total, attacker: attacker.clone() }
+                }
+                // Only a caster that has fought our side counts: an unregistered party member is not an enemy
+                ParsedLine::Casts { caster, spell, .. }
+                    if !registry.is_friendly(caster) && caster_opponents.iter().any(|name| registry.is_friendly(name)) =>
+                {
+                    AlertEvent::EnemyCast { caster: caster.clone(), spell: spell.clone() }
+                }
+                _ => return,
+            }
+        };
+        if let Ok(mut alerts) = self.alerts.lock() {
+            alerts.check_event(&rules, &event);
+        }
+    }
+
+    /// Everyone a combatant has dealt damage to or taken damage from in the current encounter
+    fn current_opponents(&self, name: &str) -> Vec<String> {
+        let Some(encounter_id) = self.state.current_encounter else {
+            return Vec::new();
+        };
+        let Ok(encounters) = self.encounters.lock() else {
+            return Vec::new();
+        };
+        encounters.get(&encounter_id)
+            .and_then(|encounter| encounter.stats.get(name))
+            .map(|stats| {
+                stats.damage_by_target_dealt.keys().chain(stats.damage_by_attacker_received.keys())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Publish the results of a batch of historical lines to the shared state
+    pub fn finish_historical(&mut self) {
+        *self.current_encounter_id.lock().unwrap() = self.state.current_encounter;
+
+        let mut encounters_lock = self.encounters.lock().unwrap();
+        for encounter in encounters_lock.values_mut() {
+            encounter.update_most_damaged();
+        }
+        // Continue the timeout logic from the most recent encounter
+        if let Some(most_recent) = encounters_lock.values().max_by_key(|e| e.end_time) {
+            self.state.last_combat_time = most_recent.end_time;
+        }
+    }
+
+    /// Add a finished encounter to the history store (fights where nobody dealt damage are skipped)
+    fn archive_encounter(&self, encounter_id: u64) {
+        if !self.persist_history {
+            return;
+        }
+        let Some(mut encounter) = self.encounters.lock().unwrap().get(&encounter_id).cloned() else {
+            return;
+        };
+        if encounter.stats.values().all(|stats| stats.total_damage_dealt == 0) {
+            return;
+        }
+        encounter.update_most_damaged();
+
+        if let Ok(mut history) = self.history.lock()
+            && let Some(log_file) = history.active_log.clone()
+        {
+            auto_save_history_entry(&mut history, HistoryEntry { log_file, encounter });
+        }
+    }
+
+    /// Clone the encounters built so far
+    pub fn snapshot(&self) -> SessionSnapshot {
+        SessionSnapshot {
+            encounters: self.encounters.lock().unwrap().clone(),
+            current_encounter_id: self.state.current_encounter,
+        }
+    }
+
+    /// Drop all encounters and parser state, e.g. when switching log files
+    pub fn reset(&mut self) {
+        self.encounters.lock().unwrap().clear();
+        *self.current_encounter_id.lock().unwrap() = None;
+        *self.encounter_counter.lock().unwrap() = 1;
+        self.state = CombatState::default();
+        self.clock = LogClock::default();
+        self.previous_cast = None;
+        if let Ok(mut tracker) = self.buff_tracker.lock() {
+            tracker.reset_log_time();
+        }
+    }
+
+    /// Copy of the current settings, falling back to defaults if the lock is poisoned
+    pub fn current_settings(&self) -> AppSettings {
+        self.settings.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
@@ -0,0 +1,3 @@
+pub mod combat_session;
+
+pub use combat_session::{CombatSession, CombatState, SessionSnapshot};
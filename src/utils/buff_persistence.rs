@@ -0,0 +1,97 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use crate::models::buffs::{BuffDefinition, BuffTracker, default_buff_definitions};
+
+/// Buff definitions, next to settings.json; written with the defaults when missing so it can be edited
+const BUFFS_FILE: &str = "buffs.json";
+
+/// Buff and effect timers running when the app last closed (only with `persist_buff_timers`)
+const BUFF_TIMERS_FILE: &str = "buff_timers.json";
+
+pub fn get_buffs_file_path() -> PathBuf {
+    PathBuf::from(BUFFS_FILE)
+}
+
+pub fn get_buff_timers_file_path() -> PathBuf {
+    PathBuf::from(BUFF_TIMERS_FILE)
+}
+
+pub fn load_buff_definitions() -> Vec<BuffDefinition> {
+    let file_path = get_buffs_file_path();
+
+    if !file_path.exists() {
+        eprintln!("No buff definitions found, writing the defaults to {}", file_path.display());
+        let definitions = default_buff_definitions();
+        if let Err(e) = save_buff_definitions(&definitions) {
+            eprintln!("Failed to write default buff definitions: {}", e);
+        }
+        return definitions;
+    }
+
+    match fs::read_to_string(&file_path) {
+        Ok(content) => {
+            match serde_json::from_str::<Vec<BuffDefinition>>(&content) {
+                Ok(definitions) => {
+                    eprintln!("Loaded {} buff definitions", definitions.len());
+                    definitions
+                }
+                Err(e) => {
+                    eprintln!("Error parsing buff definitions JSON: {}. Using defaults.", e);
+                    default_buff_definitions()
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Error reading buff definitions file: {}. Using defaults.", e);
+            default_buff_definitions()
+        }
+    }
+}
+
+pub fn save_buff_definitions(definitions: &[BuffDefinition]) -> io::Result<()> {
+    let file_path = get_buffs_file_path();
+
+    let json_content = serde_json::to_string_pretty(definitions)
+        .map_err(|e| io::Error::other(format!("JSON serialization error: {}", e)))?;
+
+    let mut file = fs::File::create(&file_path)?;
+    file.write_all(json_content.as_bytes())?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Restore the timers saved by the last run, dropping the ones that have run out since
+pub fn load_buff_timers(definitions: Vec<BuffDefinition>) -> BuffTracker {
+    let file_path = get_buff_timers_file_path();
+    let saved = fs::read_to_string(&file_path).ok()
+        .and_then(|content| match serde_json::from_str::<BuffTracker>(&content) {
+            Ok(tracker) => Some(tracker),
+            Err(e) => {
+                eprintln!("Error parsing buff timers JSON: {}. Starting without timers.", e);
+                None
+            }
+        });
+
+    let mut tracker = BuffTracker::with_definitions(definitions);
+    if let Some(saved) = saved {
+        tracker.active_buffs = saved.active_buffs;
+        tracker.effects = saved.effects;
+        tracker.remove_expired_buffs();
+        tracker.effects.remove_stale();
+        eprintln!("Restored {} running buffs", tracker.active_buffs.len());
+    }
+    tracker
+}
+
+pub fn save_buff_timers(tracker: &BuffTracker) -> io::Result<()> {
+    let file_path = get_buff_timers_file_path();
+
+    let json_content = serde_json::to_string_pretty(tracker)
+        .map_err(|e| io::Error::other(format!("JSON serialization error: {}", e)))?;
+
+    let mut file = fs::File::create(&file_path)?;
+    file.write_all(json_content.as_bytes())?;
+    file.flush()?;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use crate::models::{EncounterHistory, HistoryChange, HistoryEntry};
+
+/// One JSON `HistoryEntry` per line, next to players.json
+const HISTORY_FILE: &str = "encounters.jsonl";
+
+pub fn get_history_file_path() -> PathBuf {
+    PathBuf::from(HISTORY_FILE)
+}
+
+pub fn load_encounter_history() -> EncounterHistory {
+    let file_path = get_history_file_path();
+
+    if !file_path.exists() {
+        eprintln!("No encounter history found, starting with empty history");
+        return EncounterHistory::default();
+    }
+
+    let file = match fs::File::open(&file_path) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Error reading encounter history: {}. Starting with empty history.", e);
+            return EncounterHistory::default();
+        }
+    };
+
+    let mut lines = 0;
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        lines += 1;
+        match serde_json::from_str::<HistoryEntry>(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => eprintln!("Skipping unreadable encounter history line: {}", e),
+        }
+    }
+
+    let history = EncounterHistory::from_entries(entries);
+    eprintln!("Loaded encounter history with {} encounters", history.len());
+
+    // Replaced and unreadable lines are dropped from the file
+    if history.len() < lines {
+        let _ = save_encounter_history(&history);
+    }
+    history
+}
+
+/// Rewrite the whole history file
+pub fn save_encounter_history(history: &EncounterHistory) -> io::Result<()> {
+    let mut content = String::new();
+    let mut entries: Vec<&HistoryEntry> = history.entries().collect();
+    entries.reverse(); // Oldest first, like appended lines
+    for entry in entries {
+        content.push_str(&to_json_line(entry)?);
+    }
+    fs::write(get_history_file_path(), content)
+}
+
+fn append_history_line(line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(get_history_file_path())?;
+    file.write_all(line.as_bytes())?;
+    file.flush()
+}
+
+fn to_json_line(entry: &HistoryEntry) -> io::Result<String> {
+    let mut line = serde_json::to_string(entry)
+        .map_err(|e| io::Error::other(format!("JSON serialization error: {}", e)))?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Add a finished encounter to the history and write it out
+pub fn auto_save_history_entry(history: &mut EncounterHistory, entry: HistoryEntry) {
+    let line = to_json_line(&entry);
+    let result = match history.record(entry) {
+        HistoryChange::Unchanged => Ok(()),
+        HistoryChange::Added => line.and_then(|line| append_history_line(&line)),
+        HistoryChange::Replaced => save_encounter_history(history),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to save encounter history: {}", e);
+    }
+}
@@ -1,7 +1,13 @@
 pub mod time;
 pub mod player_persistence;
 pub mod settings_persistence;
+pub mod history_persistence;
+pub mod buff_persistence;
+pub mod sound;
 
-pub use time::get_current_timestamp;
-pub use player_persistence::{load_player_registry, auto_save_player_registry};
-pub use settings_persistence::{load_app_settings, auto_save_app_settings};
\ No newline at end of file
+pub use time::{get_current_timestamp, LogClock};
+pub use player_persistence::{load_player_registry, read_player_registry, auto_save_player_registry};
+pub use settings_persistence::{load_app_settings, auto_save_app_settings};
+pub use history_persistence::{load_encounter_history, auto_save_history_entry};
+pub use buff_persistence::{load_buff_definitions, load_buff_timers, save_buff_timers};
+pub use sound::play_sound;
\ No newline at end of file
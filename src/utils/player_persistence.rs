@@ -1,6 +1,6 @@
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use crate::models::PlayerRegistry;
 
 const PLAYERS_FILE: &str = "players.json";
@@ -13,7 +13,7 @@ pub fn load_player_registry() -> PlayerRegistry {
     let file_path = get_players_file_path();
 
     if !file_path.exists() {
-        println!("No existing player registry found, starting with empty registry");
+        eprintln!("No existing player registry found, starting with empty registry");
         return PlayerRegistry::new();
     }
 
@@ -21,7 +21,7 @@ pub fn load_player_registry() -> PlayerRegistry {
         Ok(content) => {
             match serde_json::from_str::<PlayerRegistry>(&content) {
                 Ok(mut registry) => {
-                    println!("Loaded player registry with {} players", registry.players.len());
+                    eprintln!("Loaded player registry with {} players", registry.players.len());
                     registry.cleanup_temporary_accounts();
                     // Save the cleaned up registry
                     let _ = save_player_registry(&registry);
@@ -40,6 +40,14 @@ pub fn load_player_registry() -> PlayerRegistry {
     }
 }
 
+/// Read a registry file as it is, without cleaning it up or writing it back (for headless runs)
+pub fn read_player_registry(path: &Path) -> io::Result<PlayerRegistry> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    serde_json::from_str(&content)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
 pub fn save_player_registry(registry: &PlayerRegistry) -> io::Result<()> {
     let file_path = get_players_file_path();
 
@@ -50,7 +58,7 @@ pub fn save_player_registry(registry: &PlayerRegistry) -> io::Result<()> {
     file.write_all(json_content.as_bytes())?;
     file.flush()?;
 
-    println!("Saved player registry with {} players", registry.players.len());
+    eprintln!("Saved player registry with {} players", registry.players.len());
     Ok(())
 }
 
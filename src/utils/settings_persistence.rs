@@ -14,16 +14,16 @@ pub fn load_app_settings() -> AppSettings {
     let file_path = get_settings_file_path();
 
     let mut settings = if !file_path.exists() {
-        println!("No existing settings found, using defaults");
+        eprintln!("No existing settings found, using defaults");
         AppSettings::default()
     } else {
         match fs::read_to_string(&file_path) {
             Ok(content) => {
                 match serde_json::from_str::<AppSettings>(&content) {
-                    Ok(settings) => {
-                        println!("Loaded settings: caster level {}, CHA mod {}, Divine Might: {}, Divine Shield: {}",
-                                 settings.caster_level, settings.charisma_modifier,
-                                 settings.extended_divine_might, settings.extended_divine_shield);
+                    Ok(mut settings) => {
+                        settings.migrate_legacy_buff_toggles();
+                        eprintln!("Loaded settings: caster level {}, CHA mod {}, buff modifiers: {:?}",
+                                 settings.caster_level, settings.charisma_modifier, settings.buff_modifiers);
                         settings
                     }
                     Err(e) => {
@@ -63,9 +63,8 @@ pub fn save_app_settings(settings: &AppSettings) -> io::Result<()> {
     file.write_all(json_content.as_bytes())?;
     file.flush()?;
 
-    println!("Saved settings: caster level {}, CHA mod {}, Divine Might: {}, Divine Shield: {}",
-             settings.caster_level, settings.charisma_modifier,
-             settings.extended_divine_might, settings.extended_divine_shield);
+    eprintln!("Saved settings: caster level {}, CHA mod {}, buff modifiers: {:?}",
+             settings.caster_level, settings.charisma_modifier, settings.buff_modifiers);
     Ok(())
 }
 
@@ -0,0 +1,40 @@
+use std::io;
+use std::process::{Child, Command};
+use std::thread;
+
+/// Play a sound file in the background with the player that comes with the OS
+/// (WAV via PowerShell on Windows, `afplay` on macOS, `paplay` or `aplay` elsewhere)
+pub fn play_sound(path: &str) {
+    match spawn_player(path) {
+        // Reap the player when it finishes so it doesn't linger as a zombie
+        Ok(mut child) => {
+            thread::spawn(move || {
+                let _ = child.wait();
+            });
+        }
+        Err(e) => eprintln!("Failed to play sound {}: {}", path, e),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn spawn_player(path: &str) -> io::Result<Child> {
+    use std::os::windows::process::CommandExt;
+    // Don't pop up a console window for PowerShell
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let script = format!("(New-Object Media.SoundPlayer '{}').PlaySync()", path.replace('\'', "''"));
+    Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()
+}
+
+#[cfg(target_os = "macos")]
+fn spawn_player(path: &str) -> io::Result<Child> {
+    Command::new("afplay").arg(path).spawn()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn spawn_player(path: &str) -> io::Result<Child> {
+    Command::new("paplay").arg(path).spawn()
+        .or_else(|_| Command::new("aplay").args(["-q", path]).spawn())
+}
@@ -1,6 +1,7 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::fs;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone};
 
 pub fn format_duration(seconds: u64) -> String {
     if seconds >= 60 {
@@ -12,26 +13,108 @@ pub fn format_duration(seconds: u64) -> String {
     }
 }
 
-pub fn parse_timestamp(timestamp_str: &str) -> u64 {
-    // NWN timestamp format is like "Tue Jul 29 14:10:26"
-    // Parse the time components and convert to seconds since start of day
-    if let Some(time_part) = timestamp_str.split_whitespace().nth(3) {
-        let parts: Vec<&str> = time_part.split(':').collect();
-        if parts.len() == 3 {
-            if let (Ok(hours), Ok(minutes), Ok(seconds)) = (
-                parts[0].parse::<u64>(),
-                parts[1].parse::<u64>(),
-                parts[2].parse::<u64>()
-            ) {
-                return hours * 3600 + minutes * 60 + seconds;
+/// Log timestamps look like "Tue Jul 29 14:10:26"; the year is not logged
+const LOG_DATE_FORMAT: &str = "%Y %b %d %H:%M:%S";
+/// A timestamp this far before the previous line means the log crossed New Year
+const YEAR_ROLLOVER_SECONDS: u64 = 180 * 24 * 3600;
+/// How many years back to look for one where the logged weekday matches the date
+const MAX_YEAR_LOOKBACK: i32 = 6;
+
+/// Parse a log timestamp ("Tue Jul 29 14:10:26") in the given year into Unix seconds.
+/// Log times are local wall-clock times; the weekday is not checked.
+pub fn parse_timestamp(timestamp_str: &str, year: i32) -> Option<u64> {
+    let naive = parse_naive(timestamp_str, year)?;
+    // Times skipped by a DST change don't exist locally, so read those as UTC
+    let seconds = Local.from_local_datetime(&naive).earliest()
+        .map(|local| local.timestamp())
+        .unwrap_or_else(|| naive.and_utc().timestamp());
+    u64::try_from(seconds).ok()
+}
+
+fn parse_naive(timestamp_str: &str, year: i32) -> Option<NaiveDateTime> {
+    let (_, date_time) = timestamp_str.trim().split_once(' ')?;
+    NaiveDateTime::parse_from_str(&format!("{} {}", year, date_time.trim()), LOG_DATE_FORMAT).ok()
+}
+
+/// Turns log timestamps into Unix seconds, supplying the year the log leaves out and
+/// keeping time moving forward (across midnight, New Year and DST changes)
+#[derive(Debug, Clone)]
+pub struct LogClock {
+    /// Year the log was written in, as far as we know before reading it
+    reference_year: i32,
+    /// Year of the lines being read, settled by the first timestamp
+    year: Option<i32>,
+    last_timestamp: u64,
+}
+
+impl Default for LogClock {
+    fn default() -> Self {
+        Self::new(Local::now().year())
+    }
+}
+
+impl LogClock {
+    pub fn new(reference_year: i32) -> Self {
+        Self { reference_year, year: None, last_timestamp: 0 }
+    }
+
+    /// Clock for a log file, taking the year from its last modification time
+    pub fn for_file(path: &Path) -> Self {
+        match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => Self::new(DateTime::<Local>::from(modified).year()),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Unix seconds for a log timestamp, never earlier than the previous one
+    pub fn timestamp(&mut self, timestamp_str: &str) -> Option<u64> {
+        let year = match self.year {
+            Some(year) => year,
+            None => {
+                let year = self.infer_year(timestamp_str);
+                self.year = Some(year);
+                year
             }
+        };
+
+        let mut timestamp = parse_timestamp(timestamp_str, year)?;
+        if timestamp + YEAR_ROLLOVER_SECONDS < self.last_timestamp
+            && let Some(next_year) = parse_timestamp(timestamp_str, year + 1)
+        {
+            self.year = Some(year + 1);
+            timestamp = next_year;
         }
+
+        self.last_timestamp = self.last_timestamp.max(timestamp);
+        Some(self.last_timestamp)
+    }
+
+    /// The most recent year (up to the reference year) in which the logged weekday falls on the logged date
+    fn infer_year(&self, timestamp_str: &str) -> i32 {
+        let weekday = timestamp_str.split_whitespace().next().unwrap_or_default();
+        (0..=MAX_YEAR_LOOKBACK)
+            .map(|years_back| self.reference_year - years_back)
+            .find(|&year| {
+                parse_naive(timestamp_str, year).is_some_and(|naive| naive.weekday().to_string() == weekday)
+            })
+            .unwrap_or(self.reference_year)
     }
-    
-    // Fallback: use hash if parsing fails
-    let mut hasher = DefaultHasher::new();
-    timestamp_str.hash(&mut hasher);
-    (hasher.finish() % (u32::MAX as u64)) + 1000000
+}
+
+/// Local wall-clock time of a timestamp, e.g. "14:10:26"
+pub fn format_clock_time(timestamp: u64) -> String {
+    format_local(timestamp, "%H:%M:%S")
+}
+
+/// Local date and time of a timestamp, e.g. "2025-07-29 14:10:26"
+pub fn format_date_time(timestamp: u64) -> String {
+    format_local(timestamp, "%Y-%m-%d %H:%M:%S")
+}
+
+fn format_local(timestamp: u64, format: &str) -> String {
+    Local.timestamp_opt(timestamp as i64, 0).single()
+        .map(|local| local.format(format).to_string())
+        .unwrap_or_else(|| timestamp.to_string())
 }
 
 pub fn get_current_timestamp() -> u64 {
@@ -0,0 +1,202 @@
+//! Golden-file tests for the parser.
+//!
+//! Each log in `tests/fixtures/` has its own test, listed in `golden_tests!`. The log is run
+//! through `parse_log_line` and `process_full_log_file` (which drives `process_parsed_line`),
+//! and the parsed lines, resulting encounters and logs window entries are compared with
+//! `tests/golden/<fixture>.json`.
+//!
+//! When behaviour changes on purpose, re-bless the snapshots and review the diff:
+//!
+//! ```text
+//! BLESS=1 cargo test --test golden
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, SystemTime};
+use regex::Regex;
+use serde_json::{json, Value};
+use nwn_parser::log::entry::LogEntry;
+use nwn_parser::log::watcher::process_full_log_file;
+use nwn_parser::parsing::parse_log_line;
+use nwn_parser::session::CombatSession;
+use nwn_parser::utils::LogClock;
+
+/// Fixtures are read as if NWN last wrote them on 2025-12-31 12:00 UTC, so the years
+/// inferred for their timestamps don't depend on when the repository was checked out
+const FIXTURE_MODIFIED: u64 = 1_767_182_400;
+
+/// Fixtures also run with pet damage merged into the owner's, snapshotted under `merged_pet_encounters`
+const MERGED_PET_FIXTURES: &[&str] = &["pets", "summon_window"];
+
+/// One test per fixture, so a failing fixture doesn't hide the ones after it
+macro_rules! golden_tests {
+    ($($test:ident => $fixture:literal),* $(,)?) => {
+        const FIXTURES: &[&str] = &[$($fixture),*];
+
+        $(
+            #[test]
+            fn $test() {
+                check($fixture);
+            }
+        )*
+    };
+}
+
+golden_tests! {
+    attack_rolls => "attack_rolls",
+    damage_types => "damage_types",
+    immunity => "immunity",
+    midnight => "midnight",
+    nwclient_log1 => "nwclientLog1",
+    party_kills => "party_kills",
+    pets => "pets",
+    saves => "saves",
+    segmentation => "segmentation",
+    summon_window => "summon_window",
+}
+
+#[test]
+fn every_fixture_has_a_test() {
+    let mut on_disk: Vec<String> = fs::read_dir(manifest_dir().join("tests/fixtures")).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .map(|path| path.file_stem().unwrap().to_string_lossy().into_owned())
+        .collect();
+    on_disk.sort();
+    let mut listed: Vec<&str> = FIXTURES.to_vec();
+    listed.sort_unstable();
+    assert_eq!(on_disk, listed, "add new fixtures to `golden_tests!`");
+}
+
+/// Compare one fixture with its snapshot, or rewrite the snapshot when `BLESS` is set
+fn check(name: &str) {
+    use_utc();
+    let fixture = manifest_dir().join("tests/fixtures").join(format!("{}.txt", name));
+    let golden_path = manifest_dir().join("tests/golden").join(format!("{}.json", name));
+    let actual = serde_json::to_string_pretty(&snapshot(&fixture)).unwrap() + "\n";
+    let _ = fs::remove_dir_all(fixture_copy_dir(name));
+
+    if std::env::var_os("BLESS").is_some() {
+        fs::write(&golden_path, &actual).unwrap();
+        println!("Blessed {}", golden_path.display());
+        return;
+    }
+
+    let expected = fs::read_to_string(&golden_path)
+        .unwrap_or_else(|_| panic!("missing {}", golden_path.display()))
+        .replace("\r\n", "\n");
+    if let Some(difference) = first_difference(&expected, &actual) {
+        panic!("{}: {}\n\nIf the change is intended, re-bless with `BLESS=1 cargo test --test golden`", name, difference);
+    }
+}
+
+/// Log timestamps are local times; pin the zone so snapshots match on every machine
+fn use_utc() {
+    static UTC: Once = Once::new();
+    // SAFETY: every test calls this before anything reads the environment, and `Once`
+    // makes the others wait until the variable is set.
+    UTC.call_once(|| unsafe { std::env::set_var("TZ", "UTC") });
+}
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Everything the parser makes of one fixture, with map keys sorted so the JSON is stable
+fn snapshot(fixture: &Path) -> Value {
+    let log_file = dated_copy(fixture);
+    let content = fs::read_to_string(&log_file).unwrap();
+
+    let mut clock = LogClock::for_file(&log_file);
+    let parsed_lines: Vec<Value> = content.lines()
+        .map(|line| match parse_log_line(line, &mut clock) {
+            Some(parsed) => Value::String(sort_debug_maps(&format!("{:?}", parsed))),
+            None => Value::Null,
+        })
+        .collect();
+
+    let logs_state = Arc::new(Mutex::new(Vec::new()));
+    let encounters = session_encounters(&log_file, false, logs_state.clone());
+    let log_entries: Vec<Value> = logs_state.lock().unwrap().iter()
+        .map(|entry| json!({
+            "timestamp": entry.timestamp,
+            "log_type": format!("{:?}", entry.log_type),
+            "content": entry.content,
+        }))
+        .collect();
+
+    let mut snapshot = json!({
+        "parsed_lines": parsed_lines,
+        "encounters": encounters,
+        "log_entries": log_entries,
+    });
+    let name = fixture.file_stem().unwrap().to_string_lossy();
+    if MERGED_PET_FIXTURES.contains(&name.as_ref()) {
+        snapshot["merged_pet_encounters"] = session_encounters(&log_file, true, Arc::new(Mutex::new(Vec::new())));
+    }
+    snapshot
+}
+
+/// Encounters built from a log by a fresh session
+fn session_encounters(log_file: &Path, merge_pet_damage: bool, logs_state: Arc<Mutex<Vec<LogEntry>>>) -> Value {
+    let mut session = CombatSession::new();
+    session.settings.lock().unwrap().merge_pet_damage = merge_pet_damage;
+    process_full_log_file(log_file, &mut session, logs_state).unwrap();
+
+    let mut encounters: Vec<_> = session.snapshot().encounters.into_values().collect();
+    encounters.sort_by_key(|encounter| encounter.id);
+    serde_json::to_value(&encounters).unwrap()
+}
+
+/// `HashMap`s print their entries in random order; sort the `{key: value, ..}` maps in Debug output.
+/// Keys are strings (`"Orc"`) or damage types (`Fire`, `Other("Bludgeoning")`).
+fn sort_debug_maps(debug: &str) -> String {
+    let key = r#"(?:"[^"]*"|[A-Z]\w*(?:\("[^"]*"\))?)"#;
+    let map = Regex::new(&format!(r"\{{({key}: [^,{{}}]+(?:, {key}: [^,{{}}]+)*)\}}")).unwrap();
+    map.replace_all(debug, |captures: &regex::Captures| {
+        let mut entries: Vec<&str> = captures[1].split(", ").collect();
+        entries.sort_unstable();
+        format!("{{{}}}", entries.join(", "))
+    }).into_owned()
+}
+
+/// Temporary directory for one fixture's copy, so tests running in parallel don't share it
+fn fixture_copy_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("nwn_parser_golden_{}", std::process::id())).join(name)
+}
+
+/// Copy a fixture to a temporary file with a fixed modification time
+fn dated_copy(fixture: &Path) -> PathBuf {
+    let name = fixture.file_stem().unwrap().to_string_lossy();
+    let dir = fixture_copy_dir(&name);
+    fs::create_dir_all(&dir).unwrap();
+    let copy = dir.join(fixture.file_name().unwrap());
+    fs::copy(fixture, &copy).unwrap();
+
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(FIXTURE_MODIFIED);
+    fs::File::options().write(true).open(&copy).unwrap().set_modified(modified).unwrap();
+    copy
+}
+
+/// Line number and both versions of the first line that differs
+fn first_difference(expected: &str, actual: &str) -> Option<String> {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line_number in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return None,
+            (expected, actual) if expected == actual => continue,
+            (expected, actual) => {
+                return Some(format!(
+                    "snapshot differs at line {}\n  expected: {}\n  actual:   {}",
+                    line_number,
+                    expected.unwrap_or("<end of file>"),
+                    actual.unwrap_or("<end of file>")
+                ));
+            }
+        }
+    }
+    None
+}